@@ -0,0 +1,871 @@
+const blessed = require('neo-blessed');
+
+const {bigJumpIndex} = require('./app');
+const {serverLabel} = require('./cli');
+const {copyToClipboard} = require('./clipboard');
+const {readConfigFile, writeConfigFile} = require('./config');
+const {SORT_MODES, clientName, groupName, loudestClient, serverDetails} = require('./groups');
+const {LATENCY_STEP} = require('./keys');
+const {streamMetadataLines} = require('./ui');
+const {formatVolume, snapTargets} = require('./volume');
+
+// Seconds within which a second quit keypress quits despite pending changes
+const QUIT_CONFIRM_TIME = 2;
+
+// Saved volume snapshots, keyed by name, in the config directory
+const SNAPSHOTS_FILE = 'snapshots.json';
+
+// Handlers for each action keys can be bound to, by name, each called with
+// the character and key pressed and any argument given after a colon in the
+// binding. The mixer
+// gives what they act on: the options, client, app state, screen and view,
+// the dialogs shown over them, the count typed before the key, and ways to
+// redraw, refresh, reconnect, switch servers and quit
+function createActions(mixer) {
+	const {argv, client, flashMessage, helpMessage, logger, quitMessage, screen, state, theme, view} = mixer;
+	const {chooseFromList, readInput, showMessage, showNotice, updateDisplay} = mixer;
+
+	// Volumes set by the digit keys
+	const snapVolumes = snapTargets(argv.maxVolume, argv.snapSteps);
+
+	// Time of the last quit keypress which was refused because changes were
+	// still pending
+	let quitRefusedAt = 0;
+
+	async function saveSnapshot(name) {
+		const response = await client.getStatus();
+		const snapshot = {};
+		for (const group of response.server.groups) {
+			for (const snapClient of group.clients) {
+				snapshot[snapClient.id] = snapClient.config.volume;
+			}
+		}
+		const snapshots = readConfigFile(SNAPSHOTS_FILE, {});
+		snapshots[name] = snapshot;
+		writeConfigFile(SNAPSHOTS_FILE, snapshots);
+	}
+
+	async function restoreSnapshot(snapshot) {
+		const response = await client.getStatus();
+		const present = new Set();
+		for (const group of response.server.groups) {
+			for (const snapClient of group.clients) {
+				present.add(snapClient.id);
+			}
+		}
+		const clientIds = Object.keys(snapshot).filter((clientId) => present.has(clientId));
+		const missing = Object.keys(snapshot).filter((clientId) => !present.has(clientId));
+
+		await state.withUndo(clientIds, [], async () => {
+			for (const clientId of clientIds) {
+				await client.setClientVolume(clientId, snapshot[clientId]);
+			}
+		});
+
+		if (missing.length) {
+			showNotice(`Skipped clients no longer present: ${missing.join(', ')}`);
+		}
+	}
+
+	// Ways of adjusting a client's volume, or its linked group's: by steps
+	// along the volume curve, or by a fraction of a percent
+	const adjust = (clientId, steps) => state.adjustVolume(clientId, steps);
+	const nudge = (clientId, delta) => state.nudgeVolume(clientId, delta);
+
+	// An action adjusting the focused client's volume one of those ways, by
+	// the given amount for each time the count asks for it to be repeated
+	function volumeAction(adjustment, amount) {
+		return async (ch, key) => {
+			const repeat = mixer.count;
+			if (!helpMessage.hidden) {
+				return;
+			}
+			const widget = screen.focused;
+			if (!widget || widget.type !== 'progress-bar') {
+				return;
+			}
+			await state.withUndo(state.linkedClientIds(widget.clientId), [], () => adjustment(widget.clientId, amount * repeat));
+			await updateDisplay();
+		};
+	}
+
+	// An action adjusting every client's volume together by the given steps,
+	// repeated as the count asks, and showing the new master level
+	function masterAction(steps) {
+		return async (ch, key) => {
+			const repeat = mixer.count;
+			if (!helpMessage.hidden) {
+				return;
+			}
+			const level = await state.adjustMasterVolume(steps * repeat);
+			flashMessage.display(`Master: ${formatVolume(level, argv.units)}`, 1, () => {});
+			await updateDisplay();
+		};
+	}
+
+	const actions = {};
+
+	// Select mixer
+	actions.next = (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		for (let i = 0; i < mixer.count; i++) {
+			view.form.focusNext();
+		}
+		screen.render();
+	};
+	actions.previous = (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		for (let i = 0; i < mixer.count; i++) {
+			view.form.focusPrevious();
+		}
+		screen.render();
+	};
+
+	// Move the selection by --big-jump: to the first client of another
+	// group, or by a number of clients
+	function bigJump(direction) {
+		const bars = view.shownBars;
+		if (!bars.length) {
+			return;
+		}
+		let index = bars.indexOf(screen.focused);
+		for (let i = 0; i < mixer.count; i++) {
+			index = bigJumpIndex(bars, index, direction, argv.bigJump);
+		}
+		view.focusBar(bars[index]);
+		screen.render();
+	}
+	actions.nextGroup = (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		bigJump(1);
+	};
+	actions.previousGroup = (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		bigJump(-1);
+	};
+
+	// Quit, unless --confirm-quit asks for the key again while changes are
+	// pending
+	actions.quit = async (ch, key) => {
+		if (key.name === 'escape' && !helpMessage.hidden) {
+			// Close the help box instead
+			actions.help();
+			return;
+		}
+		if (key.name === 'escape' && state.filterQuery != null) {
+			// Clear the filter instead
+			state.filterQuery = null;
+			await updateDisplay();
+			return;
+		}
+		const pending = client.hasPendingRequests() || state.hasPendingVolumes();
+		if (argv.confirmQuit && pending && Date.now() - quitRefusedAt > QUIT_CONFIRM_TIME * 1000) {
+			quitRefusedAt = Date.now();
+			quitMessage.display("Changes pending, press again to quit", QUIT_CONFIRM_TIME, () => {});
+			return;
+		}
+		mixer.quit();
+	};
+
+	// Help
+	actions.help = (ch, key) => {
+		helpMessage.toggle();
+		if (!helpMessage.hidden) {
+			helpMessage.focus();
+			helpMessage.resetScroll();
+		}
+		screen.render();
+	};
+
+	// Adjust the focused client's volume, or its linked group's, in small or
+	// large steps
+	actions.volumeUp = volumeAction(adjust, 1);
+	actions.volumeDown = volumeAction(adjust, -1);
+	actions.volumeUpLarge = volumeAction(adjust, 3);
+	actions.volumeDownLarge = volumeAction(adjust, -3);
+
+	// Snap volume to the first to tenth snap target, which are 10%, 20%,
+	// 30%, ..., 100% by default
+	actions.snap = async (ch, key, step) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		await state.withUndo(state.linkedClientIds(widget.clientId), [], () => state.applyVolume(widget.clientId, snapVolumes[parseInt(step, 10) - 1]));
+	};
+
+	// Set volume to a typed value
+	actions.typeVolume = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		let value = await readInput("Set volume", `Volume (0-${argv.maxVolume}):`);
+		if (value == null) {
+			return;
+		}
+		value = value.trim();
+		if (!/^\d+$/.test(value) || parseInt(value, 10) > argv.maxVolume) {
+			logger.log("rejected volume input:", value);
+			return;
+		}
+		await state.withUndo(state.linkedClientIds(widget.clientId), [], () => state.applyVolume(widget.clientId, parseInt(value, 10)));
+	};
+
+	// Toggle client mute
+	actions.toggleMute = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		if (widget.groupHeader) {
+			// Only the whole of a collapsed group can be muted
+			await actions.toggleGroupMute(ch, key);
+			return;
+		}
+		await state.toggleClientMute(widget.clientId);
+		await updateDisplay();
+	};
+
+	// Toggle group mute
+	actions.toggleGroupMute = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		await state.toggleGroupMute(widget.groupId);
+		await updateDisplay();
+	};
+
+	// Adjust volume in fine increments, of fractions of a percent
+	actions.volumeUpFine = volumeAction(nudge, argv.fineStep);
+	actions.volumeDownFine = volumeAction(nudge, -argv.fineStep);
+
+	// Copy the focused client's ID
+	actions.copyId = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		const method = await copyToClipboard(widget.clientId, screen.program.output);
+		flashMessage.display(method === 'system'
+			? `Copied ${widget.clientId}`
+			: `Copied ${widget.clientId} via the terminal, if it supports OSC 52`, 2, () => {});
+	};
+
+	// Set every client in the focused client's group to the volume of its
+	// loudest unmuted client, after confirming, flattening their balance
+	actions.normalize = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		const group = await client.getGroupStatus(widget.groupId);
+
+		// Muted clients don't count as the loudest, as with the marker
+		const loudest = loudestClient(group, (member) => state.exactVolume(member));
+		if (!loudest) {
+			showNotice("All clients in the group are muted");
+			return;
+		}
+		const volume = Math.round(state.exactVolume(loudest));
+		const changing = group.clients.filter((member) => state.displayedVolume(member) !== volume);
+		if (!changing.length) {
+			showNotice(`All clients in the group are already at ${formatVolume(volume, argv.units)}`);
+			return;
+		}
+		const answer = await showMessage("Normalize group", `Set ${changing.length} ${changing.length === 1 ? "client" : "clients"} to ${formatVolume(volume, argv.units)}, losing their balance? {bold}enter{/bold} sets them, {bold}esc{/bold} cancels`);
+		if (answer !== 'enter') {
+			return;
+		}
+		await state.withUndo(changing.map((member) => member.id), [], () => {
+			for (const member of changing) {
+				state.queueVolume(member.id, volume);
+			}
+		});
+	};
+
+	// Adjust all clients' volumes together
+	actions.masterUp = masterAction(1);
+	actions.masterDown = masterAction(-1);
+
+	// Mute or restore every group
+	actions.muteAll = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await state.toggleMuteAll();
+		await updateDisplay();
+	};
+
+	// Mute each client in the focused client's group individually
+	actions.toggleGroupMembersMute = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		await state.toggleGroupMembersMute(widget.groupId);
+		await updateDisplay();
+	};
+
+	// Adjust latency
+	actions.latencyUp = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		await client.adjustLatency(widget.clientId, LATENCY_STEP);
+		await updateDisplay();
+	};
+	actions.latencyDown = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		await client.adjustLatency(widget.clientId, -LATENCY_STEP);
+		await updateDisplay();
+	};
+
+	// Set every client in the focused client's group to the group's highest
+	// latency, so that they play in sync
+	actions.syncLatency = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		const group = await client.getGroupStatus(widget.groupId);
+
+		// Clients which don't report a latency are left alone
+		const known = group.clients.filter((member) => typeof member.config.latency === 'number');
+		const unknown = group.clients.length - known.length;
+		const latency = Math.max(0, ...known.map((member) => member.config.latency));
+		const behind = known.filter((member) => member.config.latency !== latency);
+		for (const member of behind) {
+			await client.setLatency(member.id, latency);
+		}
+		await updateDisplay();
+
+		const notes = [behind.length
+			? `Set the latency of ${behind.length} ${behind.length === 1 ? "client" : "clients"} to ${latency}ms`
+			: `All clients' latencies are already ${latency}ms`];
+		if (unknown) {
+			notes.push(`${unknown} ${unknown === 1 ? "client doesn't" : "clients don't"} report a latency and ${unknown === 1 ? "was" : "were"} left alone`);
+		}
+		showNotice(notes.join("\n"));
+	};
+
+	// Rename client
+	actions.rename = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		const current = await client.getClientStatus(widget.clientId);
+		const name = await readInput("Rename client", "Name (empty for host name):", clientName(current));
+		if (name == null) {
+			return;
+		}
+		await client.setClientName(widget.clientId, name.trim());
+		await updateDisplay();
+	};
+
+	// Move client to another group
+	actions.moveToGroup = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		const response = await client.getStatus();
+		const otherGroups = response.server.groups.filter((group) => group.id !== widget.groupId);
+		if (!otherGroups.length) {
+			showNotice("There are no other groups");
+			return;
+		}
+		const index = await chooseFromList("Move to group", otherGroups.map(groupName));
+		if (index == null) {
+			return;
+		}
+
+		// If this empties the old group the server will remove it
+		const target = otherGroups[index];
+		await client.setGroupClients(target.id, target.clients.map((member) => member.id).concat(widget.clientId));
+		await updateDisplay();
+	};
+
+	// Choose stream for group
+	actions.chooseStream = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		const response = await client.getStatus();
+		const streams = response.server.streams;
+		if (!streams.length) {
+			showNotice("The server has no streams");
+			return;
+		}
+		const index = await chooseFromList("Stream", streams.map((stream) => stream.id));
+		if (index == null) {
+			return;
+		}
+		await client.setGroupStream(widget.groupId, streams[index].id);
+		await updateDisplay();
+	};
+
+	// Show stream metadata
+	actions.streamInfo = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		const response = await client.getStatus();
+		const group = response.server.groups.find((group) => group.id === widget.groupId);
+		const stream = group && response.server.streams.find((stream) => stream.id === group.stream_id);
+		const lines = stream ? streamMetadataLines(stream) : [];
+		await showMessage(stream ? stream.id : "Now playing", lines.length ? lines.join("\n") : "No metadata available");
+	};
+
+	// Remove the focused client from the server, if it's disconnected, after
+	// confirming; a group left empty goes with it
+	actions.deleteClient = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		const snapClient = await client.getClientStatus(widget.clientId);
+		if (snapClient.connected) {
+			showNotice(`${blessed.escape(clientName(snapClient))} is connected, so can't be removed`);
+			return;
+		}
+		const answer = await showMessage("Remove client", `Remove ${blessed.escape(clientName(snapClient))} from the server? {bold}enter{/bold} removes it, {bold}esc{/bold} cancels`);
+		if (answer !== 'enter') {
+			return;
+		}
+
+		// Move focus to a neighbour once it's gone
+		const index = view.shownBars.indexOf(widget);
+		await client.deleteClient(widget.clientId);
+		await updateDisplay();
+		const neighbour = view.shownBars[Math.min(Math.max(0, index), view.shownBars.length - 1)];
+		if (neighbour && !view.clientSpecs[widget.clientId]) {
+			view.focusBar(neighbour);
+			screen.render();
+		}
+	};
+
+	// Show what the server reports about itself; none of it can be changed
+	// over the control interface
+	actions.serverInfo = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const response = await client.getStatus();
+		const clientCount = response.server.groups.reduce((total, group) => total + group.clients.length, 0);
+		const details = [["Server", serverLabel(client.options)]]
+			.concat(serverDetails(response))
+			.concat([
+				["Groups", String(response.server.groups.length)],
+				["Clients", String(clientCount)],
+				["Streams", String(response.server.streams.length)],
+			]);
+		const lines = details.map(([label, value]) => `{bold}${blessed.escape(label)}:{/bold} ${blessed.escape(value)}`);
+		if (argv.readOnly) {
+			lines.push('', "Changes are only being shown locally (--read-only)");
+		}
+		await showMessage("Server", lines.join("\n"));
+	};
+
+	// Send a playback command to the focused client's group's stream, as long
+	// as the stream says it can be controlled and has the given capability,
+	// or the one the properties call for
+	async function controlStream(command, capability, description) {
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		const response = await client.getStatus();
+		const group = response.server.groups.find((candidate) => candidate.id === widget.groupId);
+		const stream = group && response.server.streams.find((candidate) => candidate.id === group.stream_id);
+		const properties = (stream && stream.properties) || {};
+		const needed = typeof capability === 'function' ? capability(properties) : capability;
+		if (!properties.canControl || !properties[needed]) {
+			flashMessage.display("Stream not controllable", 1, () => {});
+			return;
+		}
+		await client.controlStream(stream.id, command);
+		flashMessage.display(`${description}: ${stream.id}`, 1, () => {});
+	}
+
+	// Play or pause, skip or go back on the focused client's group's stream
+	actions.playPause = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await controlStream('playPause', (properties) => properties.playbackStatus === 'playing' ? 'canPause' : 'canPlay', "Play/pause");
+	};
+	actions.nextTrack = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await controlStream('next', 'canGoNext', "Next");
+	};
+	actions.previousTrack = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await controlStream('previous', 'canGoPrevious', "Previous");
+	};
+
+	// Toggle solo
+	actions.solo = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar' || widget.groupHeader) {
+			return;
+		}
+		await state.toggleSolo(widget.clientId);
+		await updateDisplay();
+	};
+
+	// Undo
+	actions.undo = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await state.undo();
+		await updateDisplay();
+	};
+
+	// Filter by name
+	actions.filter = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		let query = await readInput("Filter", "Group or client name:", state.filterQuery || '');
+		if (query == null) {
+			return;
+		}
+		query = query.trim().toLowerCase();
+		if (!query.length) {
+			state.filterQuery = null;
+			await updateDisplay();
+			return;
+		}
+
+		// Refuse a filter which would hide everything
+		const response = await client.getStatus();
+		const previousQuery = state.filterQuery;
+		state.filterQuery = query;
+		const anyMatch = response.server.groups.some((group) => state.groupMatchesFilter(group) || group.clients.some((member) => state.clientMatchesFilter(member)));
+		if (!anyMatch) {
+			state.filterQuery = previousQuery;
+			showNotice(`Nothing matches "${query}"`);
+			return;
+		}
+		await updateDisplay();
+	};
+
+	// Save a snapshot
+	actions.saveSnapshot = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		let name = await readInput("Save snapshot", "Snapshot name:");
+		if (name == null || !(name = name.trim()).length) {
+			return;
+		}
+		await saveSnapshot(name);
+	};
+
+	// Restore a snapshot
+	actions.restoreSnapshot = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const snapshots = readConfigFile(SNAPSHOTS_FILE, {});
+		const names = Object.keys(snapshots).sort();
+		if (!names.length) {
+			showNotice("No snapshots have been saved");
+			return;
+		}
+		const index = await chooseFromList("Restore snapshot", names);
+		if (index == null) {
+			return;
+		}
+		await restoreSnapshot(snapshots[names[index]]);
+		await updateDisplay();
+	};
+
+	// Number each client shown and focus the one whose number is typed next
+	actions.jump = (ch, key) => {
+		if (!helpMessage.hidden || !view.shownBars.length) {
+			return;
+		}
+		const bars = view.shownBars;
+		const digits = String(bars.length).length;
+
+		// Badges over the start of each bar on screen
+		const badges = [];
+		bars.forEach((bar, index) => {
+			const coords = bar._getCoords(true);
+			if (!coords) {
+				// Scrolled out of view
+				return;
+			}
+			const badge = blessed.text({
+				position: {
+					left: coords.xi,
+					top: coords.yi,
+					width: digits,
+					height: 1,
+				},
+				content: String(index + 1).padStart(digits, '0'),
+				style: {
+					fg: theme.focusText,
+					bg: theme.focus,
+					bold: true,
+					inverse: view.monochrome,
+				},
+			});
+			screen.append(badge);
+			badges.push(badge);
+		});
+
+		const box = blessed.box({
+			position: {
+				width: 'shrink',
+				height: 'shrink',
+				left: 'center',
+				top: 'center',
+			},
+			border: 'line',
+			label: " Jump ",
+			content: `Type a client's number, or {bold}esc{/bold} to cancel`,
+			padding: {
+				left: 1,
+				right: 1,
+			},
+			style: {
+				border: {
+					fg: theme.border,
+				},
+				label: {
+					fg: theme.label,
+				},
+			},
+			tags: true,
+		});
+		screen.append(box);
+		screen.saveFocus();
+		box.focus();
+
+		// Keep the global key bindings from firing while jumping
+		screen.grabKeys = true;
+
+		let typed = '';
+		const done = (index) => {
+			screen.grabKeys = false;
+			badges.forEach((badge) => badge.destroy());
+			box.destroy();
+			screen.restoreFocus();
+			if (index != null && bars[index] && !bars[index].detached) {
+				view.focusBar(bars[index]);
+			}
+			screen.render();
+		};
+		box.on('keypress', (ch, key) => {
+			if (!/^[0-9]$/.test(ch || '')) {
+				// Anything else, including escape, cancels
+				done(null);
+				return;
+			}
+			typed += ch;
+			box.setContent(`Jump to: {bold}${typed}{/bold}`);
+			screen.render();
+			if (typed.length === digits) {
+				done(parseInt(typed, 10) - 1);
+			}
+		});
+		screen.render();
+	};
+
+	// Fetch the server's state now, rather than waiting for a change or a
+	// quiet spell
+	actions.refresh = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await mixer.refresh();
+	};
+
+	// Switch to the next server given with --server
+	actions.nextServer = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await mixer.nextServer();
+	};
+
+	// Drop the connection, if any, and reconnect immediately
+	actions.reconnect = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		await mixer.reconnect();
+	};
+
+	// Show or hide the IP address column
+	actions.toggleIp = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		state.showIp = !state.showIp;
+		await updateDisplay();
+	};
+
+	// Show or hide muted clients
+	actions.toggleHideMuted = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		state.hideMuted = !state.hideMuted;
+		await updateDisplay();
+	};
+
+	// Pin or unpin the focused client's group to the top of the list
+	actions.pin = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		state.toggleGroup(state.pinnedGroups, widget.groupId);
+		state.saveGroupSettings();
+		await updateDisplay();
+	};
+
+	// Show the focused client's group's volumes relative to its loudest
+	// client, or absolute again
+	actions.relative = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		state.toggleGroup(state.relativeGroups, widget.groupId);
+		await updateDisplay();
+	};
+
+	// Link or unlink the volumes of the focused client's group
+	actions.link = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		state.toggleGroup(state.linkedGroups, widget.groupId);
+		state.saveGroupSettings();
+		await updateDisplay();
+	};
+
+	// Fold the focused client's group to just its title row, or unfold the
+	// focused group
+	actions.collapse = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		const widget = screen.focused;
+		if (!widget || widget.type !== 'progress-bar') {
+			return;
+		}
+		state.toggleGroup(state.collapsedGroups, widget.groupId);
+		state.saveGroupSettings();
+		await updateDisplay();
+	};
+
+	// Unfold all groups
+	actions.expandAll = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		state.collapsedGroups.clear();
+		state.saveGroupSettings();
+		await updateDisplay();
+	};
+
+	// Switch to the next sort order
+	actions.cycleSort = async (ch, key) => {
+		if (!helpMessage.hidden) {
+			return;
+		}
+		state.sortMode = SORT_MODES[(SORT_MODES.indexOf(state.sortMode) + 1) % SORT_MODES.length];
+		await updateDisplay();
+	};
+
+	return actions;
+}
+
+module.exports = {
+	createActions,
+};
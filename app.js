@@ -0,0 +1,569 @@
+const events = require('events');
+
+const {readConfigFile, writeConfigFile} = require('./config');
+const {clientName} = require('./groups');
+const {adjustedVolume, scaledVolumes, withRetry} = require('./volume');
+
+// Maximum number of changes which can be undone
+const UNDO_LIMIT = 50;
+
+// Milliseconds between volume changes sent for each client, so that held
+// keys and drags don't flood the server
+const VOLUME_SEND_INTERVAL = 50;
+
+// Collapsed, pinned and linked groups, keyed by server, in the config
+// directory
+const GROUPS_FILE = 'groups.json';
+
+// IDs of pinned groups from before they were kept for each server, in the
+// config directory
+const PINNED_FILE = 'pinned.json';
+
+// What the mixer knows beyond the server's own state: how groups are shown,
+// volumes still to be sent, and what can be undone. Changes go through the
+// client; emits 'volume' with a client's ID and volume as soon as one is
+// queued, 'sent' with its ID once a queued volume has been sent or has
+// failed, 'sendFailed' with its ID and the error if it failed, and 'limit'
+// when an adjustment goes nowhere because the volume is already at its limit
+class AppState extends events.EventEmitter {
+	constructor(client, options, logger) {
+		super();
+		this.client = client;
+		this.options = options;
+		this.logger = logger;
+
+		// Label of the server whose group settings are loaded
+		this.server = null;
+
+		// IDs of groups which are folded to just their title rows
+		this.collapsedGroups = new Set();
+
+		// IDs of groups which are always listed first
+		this.pinnedGroups = new Set();
+
+		// IDs of groups whose clients' volumes move together
+		this.linkedGroups = new Set();
+
+		// IDs of groups whose volumes are shown relative to their loudest
+		// client
+		this.relativeGroups = new Set();
+
+		// Whether the IP address column is shown
+		this.showIp = options.showIp;
+
+		// Whether muted clients are left out of the list
+		this.hideMuted = options.hideMuted;
+
+		// Order of groups and clients
+		this.sortMode = options.sort;
+
+		// Case-insensitive filter on group and client names, or null
+		this.filterQuery = null;
+
+		// Group ID of each client, by client ID, as last drawn
+		this.clientGroups = {};
+
+		// When we last sent a change to each client, by ID
+		this.lastSentAt = {};
+		client.on('sent', (message) => {
+			if (message.method.startsWith('Client.Set')) {
+				this.lastSentAt[message.params.id] = Date.now();
+			}
+		});
+
+		// Each entry is a list of states to restore, one per affected client
+		// or group, recorded just before a change is made
+		this.undoStack = [];
+
+		// Latest mute state asked for, by client or group ID, so that a retry
+		// doesn't undo a newer toggle
+		this.muteTargets = {};
+
+		// Volumes waiting to be sent, and volumes sent but not yet confirmed
+		// by the server, by client ID
+		this.pendingVolumes = {};
+		this.sendingVolumes = {};
+
+		// Unrounded volumes from the last master or fine adjustment, by
+		// client ID, so that repeated adjustments accumulate rather than
+		// being lost to rounding
+		this.exactVolumes = {};
+
+		// Each client's fraction of the loudest's volume, by client ID, as of
+		// the last master adjustment made while any was audible, so that the
+		// balance comes back when the master is raised from silence
+		this.masterBalance = {};
+
+		// Mute states of each client, by group ID, from before all of a
+		// group's clients were muted individually
+		this.groupMembersMuted = {};
+
+		// Mute states of each group, by ID, from before all groups were
+		// muted, or null if they haven't been
+		this.allGroupsMuted = null;
+
+		// While solo mode is engaged this holds the soloed client's ID and
+		// the mute states of every other client from before it was engaged
+		this.soloState = null;
+	}
+
+	// Forget everything about the last server, on switching to another
+	reset() {
+		this.undoStack.length = 0;
+		this.soloState = null;
+		this.allGroupsMuted = null;
+		for (const state of [this.groupMembersMuted, this.exactVolumes, this.masterBalance, this.pendingVolumes, this.sendingVolumes, this.muteTargets, this.lastSentAt, this.clientGroups]) {
+			for (const key of Object.keys(state)) {
+				delete state[key];
+			}
+		}
+	}
+
+	// Load the collapsed, pinned and linked groups saved for a server, by
+	// label, falling back to the pinned groups saved for all servers
+	loadGroupSettings(server) {
+		this.server = server;
+		let saved;
+		try {
+			saved = readConfigFile(GROUPS_FILE, {})[server]
+				|| {pinned: readConfigFile(PINNED_FILE, [])};
+		} catch (error) {
+			this.logger.log("could not load group settings:", error.message);
+			saved = {};
+		}
+		for (const [groupIds, key] of [[this.collapsedGroups, 'collapsed'], [this.pinnedGroups, 'pinned'], [this.linkedGroups, 'linked']]) {
+			groupIds.clear();
+			for (const groupId of Array.isArray(saved[key]) ? saved[key] : []) {
+				groupIds.add(groupId);
+			}
+		}
+	}
+
+	// Save the collapsed, pinned and linked groups for the current server,
+	// leaving out any the server no longer has
+	async saveGroupSettings() {
+		try {
+			const existing = new Set((await this.client.getStatus()).server.groups.map((group) => group.id));
+			const current = (groupIds) => Array.from(groupIds).filter((groupId) => existing.has(groupId));
+			const settings = readConfigFile(GROUPS_FILE, {});
+			settings[this.server] = {
+				collapsed: current(this.collapsedGroups),
+				pinned: current(this.pinnedGroups),
+				linked: current(this.linkedGroups),
+			};
+			writeConfigFile(GROUPS_FILE, settings);
+		} catch (error) {
+			this.logger.log("could not save group settings:", error.message);
+		}
+	}
+
+	// Add a group ID to a set of them, or take it out if it's there
+	toggleGroup(groupIds, groupId) {
+		if (groupIds.has(groupId)) {
+			groupIds.delete(groupId);
+		} else {
+			groupIds.add(groupId);
+		}
+	}
+
+	// Remember which group each client is in, for linked adjustments
+	trackGroups(groups) {
+		for (const clientId of Object.keys(this.clientGroups)) {
+			delete this.clientGroups[clientId];
+		}
+		for (const group of groups) {
+			for (const member of group.clients) {
+				this.clientGroups[member.id] = group.id;
+			}
+		}
+	}
+
+	groupMatchesFilter(group) {
+		return this.filterQuery == null || group.name.toLowerCase().includes(this.filterQuery);
+	}
+
+	clientMatchesFilter(snapClient) {
+		return this.filterQuery == null || clientName(snapClient).toLowerCase().includes(this.filterQuery);
+	}
+
+	// The current states of some clients and groups, as shown, for an undo
+	// entry
+	async undoState(clientIds, groupIds = []) {
+		const entry = [];
+		for (const clientId of clientIds) {
+			const snapClient = await this.client.getClientStatus(clientId);
+			entry.push({
+				clientId: clientId,
+				volume: {
+					percent: this.displayedVolume(snapClient),
+					muted: snapClient.config.volume.muted,
+				},
+			});
+		}
+		for (const groupId of groupIds) {
+			entry.push({
+				groupId: groupId,
+				muted: await this.client.getGroupMute(groupId),
+			});
+		}
+		return entry;
+	}
+
+	// Push states recorded before a change, unless nothing in them has
+	// changed since
+	async pushUndo(entry) {
+		const current = await this.undoState(
+			entry.filter((state) => state.clientId).map((state) => state.clientId),
+			entry.filter((state) => state.groupId).map((state) => state.groupId));
+		const changed = entry.some((state) => {
+			const now = current.find((other) => state.clientId ? other.clientId === state.clientId : other.groupId === state.groupId);
+			return state.clientId
+				? now.volume.percent !== state.volume.percent || now.volume.muted !== state.volume.muted
+				: now.muted !== state.muted;
+		});
+		if (!changed) {
+			return;
+		}
+		this.undoStack.push(entry);
+		if (this.undoStack.length > UNDO_LIMIT) {
+			this.undoStack.shift();
+		}
+	}
+
+	// Make a change to some clients and groups, so that it can be undone if
+	// it changed anything, even if it failed partway
+	async withUndo(clientIds, groupIds, change) {
+		const entry = await this.undoState(clientIds, groupIds);
+		try {
+			return await change();
+		} finally {
+			await this.pushUndo(entry);
+		}
+	}
+
+	async undo() {
+		const entry = this.undoStack.pop();
+		if (!entry) {
+			return;
+		}
+		for (const state of entry) {
+			if (state.clientId) {
+				// Drop volumes still waiting to be sent, which would otherwise
+				// land after this
+				delete this.pendingVolumes[state.clientId];
+				await this.client.setClientVolume(state.clientId, state.volume);
+			} else {
+				await this.client.setGroupMute(state.groupId, state.muted);
+			}
+		}
+	}
+
+	// Toggle a client's mute, retrying once if it fails
+	async toggleClientMute(clientId) {
+		await this.withUndo([clientId], [], async () => {
+			const muted = this.muteTargets[clientId] = !await this.client.getClientMute(clientId);
+			await withRetry(() => this.client.setClientMute(clientId, muted),
+				() => this.muteTargets[clientId] !== muted, this.logger);
+		});
+	}
+
+	// Toggle a group's mute, leaving its clients' alone, retrying once if it
+	// fails
+	async toggleGroupMute(groupId) {
+		await this.withUndo([], [groupId], async () => {
+			const muted = this.muteTargets[groupId] = !await this.client.getGroupMute(groupId);
+			await withRetry(() => this.client.setGroupMute(groupId, muted),
+				() => this.muteTargets[groupId] !== muted, this.logger);
+		});
+	}
+
+	// Mute each of a group's clients, or if they're all muted already,
+	// restore the mute states they had before
+	async toggleGroupMembersMute(groupId) {
+		const members = (await this.client.getGroupStatus(groupId)).clients;
+		await this.withUndo(members.map((member) => member.id), [], async () => {
+			if (members.every((member) => member.config.volume.muted)) {
+				const previous = this.groupMembersMuted[groupId] || {};
+				delete this.groupMembersMuted[groupId];
+				for (const member of members) {
+					await this.client.setClientMute(member.id, !!previous[member.id]);
+				}
+				return;
+			}
+			this.groupMembersMuted[groupId] = {};
+			for (const member of members) {
+				this.groupMembersMuted[groupId][member.id] = member.config.volume.muted;
+				await this.client.setClientMute(member.id, true);
+			}
+		});
+	}
+
+	// Mute every group, or if they're all muted already, restore the mute
+	// states they had before
+	async toggleMuteAll() {
+		const groups = (await this.client.getStatus()).server.groups;
+		await this.withUndo([], groups.map((group) => group.id), async () => {
+			if (groups.every((group) => group.muted)) {
+				const previous = this.allGroupsMuted || {};
+				this.allGroupsMuted = null;
+				for (const group of groups) {
+					await this.client.setGroupMute(group.id, !!previous[group.id]);
+				}
+				return;
+			}
+			this.allGroupsMuted = {};
+			for (const group of groups) {
+				this.allGroupsMuted[group.id] = group.muted;
+				await this.client.setGroupMute(group.id, true);
+			}
+		});
+	}
+
+	async toggleSolo(clientId) {
+		if (this.soloState) {
+			const previous = this.soloState.muted;
+			this.soloState = null;
+			await this.withUndo(Object.keys(previous), [], async () => {
+				for (const otherId of Object.keys(previous)) {
+					await this.client.setClientMute(otherId, previous[otherId]);
+				}
+			});
+			return;
+		}
+
+		const response = await this.client.getStatus();
+		const others = [];
+		for (const group of response.server.groups) {
+			for (const other of group.clients) {
+				if (other.id !== clientId) {
+					others.push(other);
+				}
+			}
+		}
+		const soloState = this.soloState = {
+			clientId: clientId,
+			muted: {},
+		};
+		await this.withUndo(others.map((other) => other.id), [], async () => {
+			for (const other of others) {
+				soloState.muted[other.id] = other.config.volume.muted;
+				await this.client.setClientMute(other.id, true);
+			}
+		});
+	}
+
+	// The volume to show for a client: one waiting to be sent or confirmed,
+	// or else the server's
+	displayedVolume(snapClient) {
+		if (this.pendingVolumes[snapClient.id] != null) {
+			return this.pendingVolumes[snapClient.id];
+		}
+		if (this.sendingVolumes[snapClient.id] != null) {
+			return this.sendingVolumes[snapClient.id];
+		}
+		return snapClient.config.volume.percent;
+	}
+
+	// Whether any volumes are still waiting to be sent
+	hasPendingVolumes() {
+		return Object.keys(this.pendingVolumes).length > 0;
+	}
+
+	// Queue a client's volume to be sent, replacing any other change made
+	// before the next send
+	queueVolume(clientId, percent) {
+		const queued = this.pendingVolumes[clientId] != null;
+		this.pendingVolumes[clientId] = percent;
+		this.emit('volume', clientId, percent);
+		if (queued) {
+			return;
+		}
+		setTimeout(async () => {
+			if (this.pendingVolumes[clientId] == null) {
+				// Dropped by undoing or on switching servers
+				return;
+			}
+			const volume = this.sendingVolumes[clientId] = this.pendingVolumes[clientId];
+			delete this.pendingVolumes[clientId];
+			try {
+				await withRetry(() => this.client.setVolume(clientId, volume),
+					() => this.pendingVolumes[clientId] != null || this.sendingVolumes[clientId] !== volume, this.logger);
+			} catch (error) {
+				this.emit('sendFailed', clientId, error);
+			} finally {
+				// Go back to the server's volume once it has confirmed this
+				// one, or if it failed, unless another has been sent since
+				if (this.sendingVolumes[clientId] === volume) {
+					delete this.sendingVolumes[clientId];
+				}
+				this.emit('sent', clientId);
+			}
+		}, VOLUME_SEND_INTERVAL);
+	}
+
+	// Send the volumes still waiting in the queue straight away, resolving
+	// once the server has them or they've failed
+	flushVolumes() {
+		const sends = [];
+		for (const clientId of Object.keys(this.pendingVolumes)) {
+			const volume = this.pendingVolumes[clientId];
+			delete this.pendingVolumes[clientId];
+			sends.push(this.client.setVolume(clientId, volume).catch((error) => {
+				this.logger.log(`could not send volume of ${clientId} on quitting:`, error.message);
+			}));
+		}
+		return Promise.all(sends);
+	}
+
+	// A client's ID, or if its group is linked, or collapsed so that only
+	// the whole group can be adjusted, the IDs of all the clients in its
+	// group
+	linkedClientIds(clientId) {
+		const groupId = this.clientGroups[clientId];
+		if (!this.linkedGroups.has(groupId) && !this.collapsedGroups.has(groupId)) {
+			return [clientId];
+		}
+		return Object.keys(this.clientGroups).filter((id) => this.clientGroups[id] === groupId);
+	}
+
+	// Set a client's volume, or every client's in its group if it's linked
+	applyVolume(clientId, percent) {
+		for (const id of this.linkedClientIds(clientId)) {
+			this.queueVolume(id, percent);
+		}
+	}
+
+	// Adjust a client's volume, or every client's in its group by the same
+	// steps if it's linked
+	async adjustVolume(clientId, steps) {
+		let moved = false;
+		for (const id of this.linkedClientIds(clientId)) {
+			const percent = this.displayedVolume(await this.client.getClientStatus(id));
+			const adjusted = adjustedVolume(percent, steps, this.options.curve, this.options.maxVolume);
+			if (adjusted !== percent) {
+				this.queueVolume(id, adjusted);
+				moved = true;
+			}
+		}
+		if (!moved && steps !== 0) {
+			this.emit('limit');
+		}
+	}
+
+	// A client's unrounded volume, unless it has changed since it was
+	// remembered, in which case the volume being sent or shown
+	exactVolume(snapClient) {
+		const remembered = this.exactVolumes[snapClient.id];
+		const percent = this.displayedVolume(snapClient);
+		return remembered != null && Math.round(remembered) === percent ? remembered : percent;
+	}
+
+	// Adjust a client's volume by a fraction of a percent, or every client's
+	// in its group by the same fraction if it's linked
+	async nudgeVolume(clientId, delta) {
+		let moved = false;
+		for (const id of this.linkedClientIds(clientId)) {
+			const snapClient = await this.client.getClientStatus(id);
+			const exact = this.exactVolume(snapClient);
+			const volume = Math.min(this.options.maxVolume, Math.max(0, exact + delta));
+			if (volume === exact) {
+				continue;
+			}
+			moved = true;
+			this.exactVolumes[id] = volume;
+			if (Math.round(volume) !== this.displayedVolume(snapClient)) {
+				this.queueVolume(id, Math.round(volume));
+			}
+		}
+		if (!moved && delta !== 0) {
+			this.emit('limit');
+		}
+	}
+
+	// Adjust every client's volume together, scaling them all so the
+	// loudest moves by the given steps, and return the new master level
+	async adjustMasterVolume(steps) {
+		const response = await this.client.getStatus();
+		const members = [];
+		for (const group of response.server.groups) {
+			members.push(...group.clients);
+		}
+
+		const volumes = {};
+		for (const member of members) {
+			volumes[member.id] = this.exactVolume(member);
+		}
+
+		const scaled = scaledVolumes(volumes, steps, this.options.curve, this.options.maxVolume, this.masterBalance);
+		if (scaled.target === scaled.loudest && steps !== 0) {
+			this.emit('limit');
+			return scaled.target;
+		}
+		if (scaled.loudest > 0) {
+			for (const member of members) {
+				this.masterBalance[member.id] = volumes[member.id] / scaled.loudest;
+			}
+		}
+		await this.withUndo(members.map((member) => member.id), [], async () => {
+			for (const member of members) {
+				const volume = scaled.volumes[member.id];
+				this.exactVolumes[member.id] = volume;
+				if (Math.round(volume) !== member.config.volume.percent) {
+					await this.client.setVolume(member.id, Math.round(volume));
+				}
+			}
+		});
+		return scaled.target;
+	}
+}
+
+// Index of the bar to select on a big jump from the one at the given index,
+// out of the bars shown, each with its group ID: to the start of the next or
+// previous group if the jump is 'group', or else by that many bars; -1 if
+// none are shown
+function bigJumpIndex(bars, index, direction, jump) {
+	if (!bars.length) {
+		return -1;
+	}
+	if (index < 0) {
+		return 0;
+	}
+	if (jump !== 'group') {
+		return Math.min(bars.length - 1, Math.max(0, index + direction * jump));
+	}
+
+	// Back to the start of this group, then on to the start of the next or
+	// previous one
+	const groupStart = (at) => {
+		while (at > 0 && bars[at - 1].groupId === bars[at].groupId) {
+			at--;
+		}
+		return at;
+	};
+	const start = groupStart(index);
+	if (direction > 0) {
+		let next = start;
+		while (next < bars.length && bars[next].groupId === bars[start].groupId) {
+			next++;
+		}
+		// Stay put in the last group
+		return next < bars.length ? next : index;
+	}
+	return start > 0 ? groupStart(start - 1) : start;
+}
+
+// The nearest of the bars shown to one which has been hidden, looking
+// after it in display order first and then before it, or null if none is
+// shown
+function nearestShownBar(allBars, shownBars, bar) {
+	const at = allBars.indexOf(bar);
+	return allBars.slice(at + 1).find((other) => shownBars.includes(other))
+		|| allBars.slice(0, Math.max(0, at)).reverse().find((other) => shownBars.includes(other))
+		|| null;
+}
+
+module.exports = {
+	AppState,
+	bigJumpIndex,
+	nearestShownBar,
+};
@@ -0,0 +1,394 @@
+const {Console} = require('console');
+const dns = require('dns');
+const fs = require('fs');
+const yargs = require('yargs');
+
+const {configPath, readConfigFile} = require('./config');
+const {SORT_MODES} = require('./groups');
+const {parseTheme} = require('./theme');
+
+// Port of Snapcast's TCP control interface
+const DEFAULT_PORT = 1705;
+
+// Parse a server given as host, host:port, [IPv6 address]:port, a bare IPv6
+// address or unix:/path/to/socket into options for net.createConnection
+function parseServer(server) {
+	if (server.startsWith('unix:')) {
+		const path = server.slice('unix:'.length);
+		if (!fs.existsSync(path)) {
+			throw new Error(`Socket ${path} does not exist`);
+		}
+		return {path: path};
+	}
+
+	let host = server;
+	let port = null;
+	const bracketed = /^\[([^\]]+)\](?::(.*))?$/.exec(server);
+	if (bracketed) {
+		host = bracketed[1];
+		port = bracketed[2];
+	} else if (server.indexOf(':') !== server.lastIndexOf(':')) {
+		// More than one colon: a bare IPv6 address, without a port
+	} else if (server.includes(':')) {
+		const colon = server.indexOf(':');
+		host = server.slice(0, colon);
+		port = server.slice(colon + 1);
+	}
+
+	if (!host.length) {
+		throw new Error(`Missing host in ${server}`);
+	}
+	if (port == null) {
+		return {host: host, port: DEFAULT_PORT};
+	}
+	if (!/^\d+$/.test(port) || !(parseInt(port, 10) > 0 && parseInt(port, 10) < 65536)) {
+		throw new Error(`Invalid port in ${server}`);
+	}
+	return {host: host, port: parseInt(port, 10)};
+}
+
+// Parse an address to listen on, given as a port, host:port or
+// [IPv6 address]:port, where a bare port means only this machine
+function parseListenAddress(address) {
+	const match = /^(?:(?:\[([^\]]+)\]|([^:]+)):)?(\d+)$/.exec(address);
+	if (!match || !(parseInt(match[3], 10) < 65536)) {
+		throw new Error(`Invalid address to listen on ${address}; expected port or host:port`);
+	}
+	return {host: match[1] || match[2] || '127.0.0.1', port: parseInt(match[3], 10)};
+}
+
+// Parse a duration such as 500ms, 5s or 2m, or a plain number of seconds,
+// into milliseconds
+function parseDuration(duration) {
+	const match = /^(\d+(?:\.\d+)?)\s*(ms|s|m)?$/.exec(String(duration).trim());
+	if (!match) {
+		throw new Error(`Invalid duration ${duration}`);
+	}
+	const value = parseFloat(match[1]) * {ms: 1, s: 1000, m: 60000}[match[2] || 's'];
+	if (!(value > 0)) {
+		throw new Error(`Duration ${duration} must be positive`);
+	}
+	return value;
+}
+
+// Describe a server's connection options as they'd be given with --server
+function serverLabel(server) {
+	if (server.path) {
+		return `unix:${server.path}`;
+	}
+	return server.host.includes(':') ? `[${server.host}]:${server.port}` : `${server.host}:${server.port}`;
+}
+
+// State remembered between runs, in the config directory
+const STATE_FILE = 'state.json';
+
+// Parse command line arguments, such as process.argv.slice(2), into options;
+// without --server, the last server used is chosen, or else the local one
+function parseArgs(args) {
+	const argv = yargs(args)
+		.command('$0', "Run the interactive mixer")
+		.command('set', "Set a client's volume and exit", (yargs) => yargs
+			.option('client', {
+				describe: "ID or name of the client",
+				type: 'string',
+				demandOption: true,
+			})
+			.option('volume', {
+				describe: "Volume from 0 to --max-volume, which is 100 unless raised",
+				type: 'number',
+				demandOption: true,
+			})
+			.check((argv) => {
+				if (!(argv.volume >= 0 && argv.volume <= argv.maxVolume)) {
+					throw new Error(`Volume must be from 0 to ${argv.maxVolume}`);
+				}
+				return true;
+			}))
+		.command('status', "Print the state of all groups and clients and exit", (yargs) => yargs
+			.option('json', {
+				describe: "Print as JSON",
+				type: 'boolean',
+				default: false,
+			}))
+		.command('info', "Print the server's version and host and exit")
+		.option('server', {
+			describe: "Snapcast server as host, host:port, [IPv6 address]:port or unix:/path/to/socket; give more than once to switch between servers",
+			type: 'string',
+			defaultDescription: `the last server used, or localhost:${DEFAULT_PORT}`,
+			coerce: (servers) => [].concat(servers).map(parseServer),
+		})
+		.option('confirm-quit', {
+			describe: "Ask for confirmation before quitting while changes are still being sent",
+			type: 'boolean',
+			default: false,
+		})
+		.option('config', {
+			describe: "Path to the config file",
+			type: 'string',
+			default: configPath('config.json'),
+		})
+		.option('theme', {
+			describe: "Colours to override, as key=colour pairs separated by commas, for example gauge=green,focus=magenta",
+			type: 'string',
+			coerce: parseTheme,
+		})
+		.option('color', {
+			describe: "Use colours; turn them off with --no-color or by setting NO_COLOR",
+			type: 'boolean',
+			default: true,
+		})
+		.option('ascii', {
+			describe: "Only use ASCII symbols, overriding detection of Unicode support",
+			type: 'boolean',
+		})
+		.option('unicode', {
+			describe: "Use Unicode symbols, overriding detection of Unicode support",
+			type: 'boolean',
+		})
+		.conflicts('ascii', 'unicode')
+		.option('reconnect-max-delay', {
+			describe: "Longest wait between attempts to reconnect, in seconds",
+			type: 'number',
+			default: 30,
+		})
+		.option('max-reconnect-attempts', {
+			describe: "Number of failed attempts to reconnect before giving up and waiting for a keypress, or 0 to never give up",
+			type: 'number',
+			default: 0,
+		})
+		.option('quiet-timeout', {
+			describe: "How long the server can be silent before checking it is still there, such as 30s or 2m; raise this on slow links",
+			type: 'string',
+			default: '30s',
+			coerce: parseDuration,
+		})
+		.option('response-timeout', {
+			describe: "How long to wait for the server to answer that check before treating the connection as stale and reconnecting, such as 5s; a few seconds suits most networks",
+			type: 'string',
+			default: '5s',
+			coerce: parseDuration,
+		})
+		.option('show-ip', {
+			describe: "Show each client's IP address next to its name",
+			type: 'boolean',
+			default: false,
+		})
+		.option('hide-muted', {
+			describe: "Leave muted clients out of the list, keeping their groups' titles",
+			type: 'boolean',
+			default: false,
+		})
+		.option('sort', {
+			describe: "Order of groups and of the clients within them",
+			choices: SORT_MODES,
+			default: 'name',
+		})
+		.option('footer', {
+			describe: "Show a line of key hints at the bottom; turn it off with --no-footer",
+			type: 'boolean',
+			default: true,
+		})
+		.option('log-file', {
+			describe: "Append log messages to this file instead of stderr",
+			type: 'string',
+		})
+		.option('big-jump', {
+			describe: "Where the shift-up and shift-down keys move the selection: to the previous or next group, or by this many clients",
+			type: 'string',
+			default: 'group',
+			coerce: (jump) => {
+				if (jump === 'group') {
+					return jump;
+				}
+				if (!/^\d+$/.test(jump) || !(parseInt(jump, 10) >= 1)) {
+					throw new Error(`Invalid --big-jump ${jump}; expected "group" or a number of clients`);
+				}
+				return parseInt(jump, 10);
+			},
+		})
+		.option('fine-step', {
+			describe: "Percentage by which the fine volume keys adjust, accumulating across presses",
+			type: 'number',
+			default: 0.5,
+		})
+		.option('read-only', {
+			describe: "Never change anything on the server; changes are only shown locally",
+			type: 'boolean',
+			default: false,
+		})
+		.option('focus', {
+			describe: "ID or name of the client to select on starting, instead of the first",
+			type: 'string',
+		})
+		.option('bell', {
+			describe: "Ring the terminal bell when a volume is already at its limit; turn it off with --no-bell",
+			type: 'boolean',
+			default: true,
+		})
+		.option('save-state', {
+			describe: "Remember the server and selected client for next time; turn it off with --no-save-state",
+			type: 'boolean',
+			default: true,
+		})
+		.option('prefer', {
+			describe: "Address family to try first when a server's name resolves to both, falling back to the other",
+			choices: ['ipv4', 'ipv6'],
+		})
+		.option('columns', {
+			describe: "Number of columns of groups, or 0 for as many as fit on wide terminals",
+			type: 'number',
+			default: 1,
+		})
+		.option('emit-events', {
+			describe: "Write a line of JSON for each volume or mute change to this file or named pipe, or - for stdout outside the mixer",
+			type: 'string',
+		})
+		.option('control-fifo', {
+			describe: "Named pipe to read commands from while running, one per line: \"vol <client> <0-max volume>\" or \"mute <group> [on|off]\"",
+			type: 'string',
+		})
+		.option('http-listen', {
+			describe: "Serve an HTTP interface for listing clients and setting their volumes on this port or host:port; a bare port listens only on this machine, and 0.0.0.0:port on all interfaces",
+			type: 'string',
+			coerce: parseListenAddress,
+		})
+		.option('max-volume', {
+			describe: "Highest volume which can be set, for servers which allow boosting volumes above 100%",
+			type: 'number',
+			default: 100,
+		})
+		.option('snap-steps', {
+			describe: "Number of steps from silence to full volume for the digit keys, so that 20 makes 1 set 5%, 2 set 10% and so on",
+			type: 'number',
+			default: 10,
+		})
+		.check((argv) => {
+			if (!(argv.snapSteps >= 1)) {
+				throw new Error("--snap-steps must be at least 1");
+			}
+			if (!(Number.isInteger(argv.maxVolume) && argv.maxVolume >= 1)) {
+				throw new Error("--max-volume must be a whole number of at least 1");
+			}
+			if (!(Number.isInteger(argv.columns) && argv.columns >= 0)) {
+				throw new Error("--columns must be a whole number of at least 0");
+			}
+			if (!(argv.fineStep > 0)) {
+				throw new Error("--fine-step must be greater than 0");
+			}
+			if (!(argv.reconnectMaxDelay > 0)) {
+				throw new Error("--reconnect-max-delay must be greater than 0");
+			}
+			if (!(Number.isInteger(argv.maxReconnectAttempts) && argv.maxReconnectAttempts >= 0)) {
+				throw new Error("--max-reconnect-attempts must be a whole number of at least 0");
+			}
+			return true;
+		})
+		.option('gradient', {
+			describe: "Colour volume gauges by level, from gaugeLow through gaugeMid to gaugeHigh in the theme",
+			type: 'boolean',
+			default: false,
+		})
+		.option('once', {
+			describe: "Draw the mixer once, as for a screenshot, and exit",
+			type: 'boolean',
+			default: false,
+		})
+		.option('dump-ui', {
+			describe: "Draw the mixer once and exit, then write what was drawn to stdout as plain text",
+			type: 'boolean',
+			default: false,
+		})
+		.option('units', {
+			describe: "Units in which to display volumes",
+			choices: ['percent', 'db'],
+			default: 'percent',
+		})
+		.option('curve', {
+			describe: "How volume adjustment keys behave: linear steps of one percent, or log steps of one decibel for a more even change in perceived loudness",
+			choices: ['linear', 'log'],
+			default: 'linear',
+		})
+		.help()
+		.argv;
+
+	// Without --server, use the last server, or failing that the local one
+	if (!argv.server) {
+		const savedState = readSavedState(argv);
+		let server = null;
+		try {
+			server = typeof savedState.server === 'string' ? parseServer(savedState.server) : null;
+		} catch (error) {
+			// Fall back to the default
+		}
+		argv.server = [server || parseServer(`localhost:${DEFAULT_PORT}`)];
+	}
+
+	// Resolve host names to the preferred address family where there is a
+	// choice
+	if (argv.prefer) {
+		const family = argv.prefer === 'ipv6' ? 6 : 4;
+		for (const server of argv.server) {
+			if (server.host) {
+				server.lookup = (hostname, options, callback) => {
+					dns.lookup(hostname, Object.assign({}, options, {all: true, family: 0}), (error, addresses) => {
+						if (error) {
+							callback(error);
+							return;
+						}
+						const sorted = addresses.filter((candidate) => candidate.family === family)
+							.concat(addresses.filter((candidate) => candidate.family !== family));
+						if (options.all) {
+							callback(null, sorted);
+						} else {
+							callback(null, sorted[0].address, sorted[0].family);
+						}
+					});
+				};
+			}
+		}
+	}
+
+	return argv;
+}
+
+// A logger whose messages go to stderr, or to a file written synchronously
+// so that nothing is lost when exiting
+function createLogger(logFile) {
+	const logStream = logFile ? (() => {
+		const fd = fs.openSync(logFile, 'a');
+		return {
+			write: (data) => fs.writeSync(fd, data),
+		};
+	})() : process.stderr;
+	// The file isn't a real stream, so has no error events to ignore
+	const logConsole = new Console({stdout: logStream, stderr: logStream, ignoreErrors: false});
+	return {
+		log: (...args) => logConsole.log(new Date().toISOString(), ...args),
+	};
+}
+
+// The server and selected client from the last run, unless turned off; an
+// unreadable state file is ignored
+function readSavedState(argv) {
+	if (!argv.saveState) {
+		return {};
+	}
+	try {
+		const state = readConfigFile(STATE_FILE, {});
+		return state && typeof state === 'object' ? state : {};
+	} catch (error) {
+		return {};
+	}
+}
+
+module.exports = {
+	DEFAULT_PORT,
+	STATE_FILE,
+	createLogger,
+	parseArgs,
+	parseDuration,
+	parseListenAddress,
+	parseServer,
+	readSavedState,
+	serverLabel,
+};
@@ -0,0 +1,53 @@
+const {spawn} = require('child_process');
+
+// Commands which put their standard input on the system clipboard, in order
+// of preference for this platform and session
+function clipboardCommands() {
+	if (process.platform === 'darwin') {
+		return [['pbcopy']];
+	}
+	if (process.platform === 'win32') {
+		return [['clip']];
+	}
+	const commands = [];
+	if (process.env.WAYLAND_DISPLAY) {
+		commands.push(['wl-copy']);
+	}
+	if (process.env.DISPLAY) {
+		commands.push(['xclip', '-selection', 'clipboard'], ['xsel', '--clipboard', '--input']);
+	}
+	return commands;
+}
+
+// Run a clipboard command, resolving to whether it succeeded
+function runCommand([command, ...args], text) {
+	return new Promise((resolve) => {
+		const child = spawn(command, args, {stdio: ['pipe', 'ignore', 'ignore']});
+		child.on('error', () => resolve(false));
+		child.on('close', (code) => resolve(code === 0));
+		child.stdin.on('error', () => {});
+		child.stdin.end(text);
+	});
+}
+
+// Escape sequence asking the terminal to set the clipboard, which works over
+// SSH in terminals which support it
+function osc52(text) {
+	return `\x1b]52;c;${Buffer.from(text).toString('base64')}\x07`;
+}
+
+// Copy text to the system clipboard, or failing that write an OSC 52 escape
+// sequence to the given stream; resolves to 'system' or 'osc52' accordingly
+async function copyToClipboard(text, output) {
+	for (const command of clipboardCommands()) {
+		if (await runCommand(command, text)) {
+			return 'system';
+		}
+	}
+	output.write(osc52(text));
+	return 'osc52';
+}
+
+module.exports = {
+	copyToClipboard,
+};
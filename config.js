@@ -0,0 +1,42 @@
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+function configDir() {
+	const base = process.env.XDG_CONFIG_HOME || path.join(os.homedir(), '.config');
+	return path.join(base, 'snapmixer');
+}
+
+function configPath(filename) {
+	return path.join(configDir(), filename);
+}
+
+// Read and parse a JSON file, returning the fallback value if it doesn't exist
+function readJsonFile(file, fallback) {
+	try {
+		return JSON.parse(fs.readFileSync(file, 'utf8'));
+	} catch (error) {
+		if (error.code === 'ENOENT') {
+			return fallback;
+		}
+		throw error;
+	}
+}
+
+// Read and parse a JSON file from the config directory,
+// returning the fallback value if it doesn't exist
+function readConfigFile(filename, fallback) {
+	return readJsonFile(configPath(filename), fallback);
+}
+
+function writeConfigFile(filename, data) {
+	fs.mkdirSync(configDir(), {recursive: true});
+	fs.writeFileSync(configPath(filename), JSON.stringify(data, null, '\t') + '\n');
+}
+
+module.exports = {
+	configPath,
+	readConfigFile,
+	readJsonFile,
+	writeConfigFile,
+};
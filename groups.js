@@ -0,0 +1,141 @@
+// Orders in which groups and clients can be sorted
+const SORT_MODES = ['name', 'volume', 'ip', 'id'];
+
+function clientName(client) {
+	return client.config.name.length ? client.config.name : client.host.name;
+}
+
+function groupName(group) {
+	return group.name.length ? group.name : group.clients.map(clientName).join(", ");
+}
+
+// Names shared by more than one client
+function duplicateNames(groups) {
+	const seen = new Set();
+	const duplicates = new Set();
+	for (const group of groups) {
+		for (const client of group.clients) {
+			const name = clientName(client);
+			if (seen.has(name)) {
+				duplicates.add(name);
+			}
+			seen.add(name);
+		}
+	}
+	return duplicates;
+}
+
+// A few characters from the end of a client's ID, to tell apart clients
+// with the same name
+function shortId(client) {
+	return client.id.replace(/[^0-9a-z]/gi, '').slice(-4);
+}
+
+// Sort a copy of a list, working out each item's key once rather than on
+// every comparison, and keeping ties in their original order
+function stableSort(items, key, compare) {
+	return items
+		.map((item, index) => ({item, index, key: key(item)}))
+		.sort((a, b) => compare(a.key, b.key) || a.index - b.index)
+		.map((entry) => entry.item);
+}
+
+function compareText(a, b) {
+	return a.localeCompare(b, undefined, {numeric: true, sensitivity: 'base'});
+}
+
+function compareLoudest(a, b) {
+	return b - a;
+}
+
+// Sort clients by name, volume (loudest first), IP address or ID
+function sortClients(clients, mode) {
+	return stableSort(clients, {
+		name: clientName,
+		volume: (client) => client.config.volume.percent,
+		ip: (client) => client.host.ip,
+		id: (client) => client.id,
+	}[mode], mode === 'volume' ? compareLoudest : compareText);
+}
+
+// Sort groups in the same way, using the loudest or first-sorted client to
+// stand for the group's volume or IP address, with any pinned groups first
+function sortGroups(groups, mode, pinned = new Set()) {
+	const key = {
+		name: groupName,
+		volume: groupVolume,
+		ip: (group) => group.clients.reduce((first, client) => first == null || compareText(client.host.ip, first) < 0 ? client.host.ip : first, null) || '',
+		id: (group) => group.id,
+	}[mode];
+	const compare = mode === 'volume' ? compareLoudest : compareText;
+	return stableSort(groups, (group) => ({pinned: pinned.has(group.id), key: key(group)}),
+		(a, b) => b.pinned - a.pinned || compare(a.key, b.key));
+}
+
+// A group's overall level, taken from its loudest client
+function groupVolume(group) {
+	return Math.max(0, ...group.clients.map((client) => client.config.volume.percent));
+}
+
+// The loudest of a group's unmuted clients, or null if they're all muted;
+// volumes are read with the given function so that unrounded ones can be
+// compared
+function loudestClient(group, volume = (client) => client.config.volume.percent) {
+	let loudest = null;
+	for (const client of group.clients) {
+		if (!client.config.volume.muted && (!loudest || volume(client) > volume(loudest))) {
+			loudest = client;
+		}
+	}
+	return loudest;
+}
+
+// Whether nothing in a group can be heard
+function groupSilent(group) {
+	return group.muted || group.clients.every((client) => client.config.volume.muted);
+}
+
+// Find a client by ID, or failing that by name
+function findClient(groups, idOrName) {
+	const clients = [];
+	for (const group of groups) {
+		clients.push(...group.clients);
+	}
+	return clients.find((client) => client.id === idOrName)
+		|| clients.find((client) => clientName(client) === idOrName);
+}
+
+// The server's version, or null if it doesn't report one
+function serverVersion(status) {
+	const server = status.server.server;
+	return (server && server.snapserver && server.snapserver.version) || null;
+}
+
+// Labelled details of the server, as far as it reports them
+function serverDetails(status) {
+	const server = status.server.server || {};
+	const details = [["Snapserver version", serverVersion(status) || "unknown"]];
+	if (server.snapserver && server.snapserver.controlProtocolVersion != null) {
+		details.push(["Control protocol version", String(server.snapserver.controlProtocolVersion)]);
+	}
+	if (server.host) {
+		details.push(["Host", `${server.host.name}${server.host.os ? ` (${server.host.os})` : ''}`]);
+	}
+	return details;
+}
+
+module.exports = {
+	SORT_MODES,
+	clientName,
+	duplicateNames,
+	findClient,
+	groupName,
+	groupSilent,
+	groupVolume,
+	loudestClient,
+	serverDetails,
+	serverVersion,
+	shortId,
+	sortClients,
+	sortGroups,
+};
@@ -0,0 +1,95 @@
+const http = require('http');
+
+// Largest request body accepted, in bytes
+const MAX_BODY = 10000;
+
+function sendJson(response, status, body) {
+	response.writeHead(status, {'Content-Type': 'application/json'});
+	response.end(JSON.stringify(body) + '\n');
+}
+
+// Read a request's body, rejecting if it's too large
+function readBody(request) {
+	return new Promise((resolve, reject) => {
+		let body = '';
+		request.setEncoding('utf8');
+		request.on('data', (data) => {
+			body += data;
+			if (body.length > MAX_BODY) {
+				reject(new Error("Request body too large"));
+				request.destroy();
+			}
+		});
+		request.on('end', () => resolve(body));
+		request.on('error', reject);
+	});
+}
+
+// The volume asked for in a request body, given as JSON such as
+// {"volume": 40} or as a bare number, or null if it's invalid
+function requestedVolume(body, maxVolume) {
+	let value;
+	try {
+		value = JSON.parse(body);
+	} catch (error) {
+		return null;
+	}
+	const volume = value && typeof value === 'object' ? value.volume : value;
+	return typeof volume === 'number' && volume >= 0 && volume <= maxVolume ? Math.round(volume) : null;
+}
+
+// Serve a small JSON interface for controlling volumes over HTTP:
+//
+//     GET /clients                     lists the clients and their volumes
+//     POST /clients/<id or name>/volume  sets a client's volume
+//
+// The handlers do the work: listClients() resolves to the list of clients,
+// and setVolume(idOrName, volume) to the client changed, or null if there's
+// no such client; volumes up to handlers.maxVolume are accepted
+function startHttpControl(address, handlers, logger) {
+	const server = http.createServer(async (request, response) => {
+		const path = request.url.split('?')[0];
+		const volumeMatch = /^\/clients\/([^/]+)\/volume$/.exec(path);
+		try {
+			if (path === '/clients' && request.method === 'GET') {
+				sendJson(response, 200, await handlers.listClients());
+			} else if (volumeMatch && request.method === 'POST') {
+				// Insisting on JSON keeps web pages from setting volumes with
+				// simple cross-site form posts, which can't send it
+				if (!/^application\/json\s*(;|$)/i.test(request.headers['content-type'] || '')) {
+					sendJson(response, 415, {error: "Expected Content-Type: application/json"});
+					return;
+				}
+				const volume = requestedVolume(await readBody(request), handlers.maxVolume);
+				if (volume == null) {
+					sendJson(response, 400, {error: `Expected a volume from 0 to ${handlers.maxVolume}, such as {"volume": 40}`});
+					return;
+				}
+				const changed = await handlers.setVolume(decodeURIComponent(volumeMatch[1]), volume);
+				if (!changed) {
+					sendJson(response, 404, {error: "No such client"});
+					return;
+				}
+				sendJson(response, 200, changed);
+			} else if (path === '/clients' || volumeMatch) {
+				sendJson(response, 405, {error: "Method not allowed"});
+			} else {
+				sendJson(response, 404, {error: "Not found"});
+			}
+		} catch (error) {
+			logger.log(`HTTP ${request.method} ${path} failed:`, error.message);
+			sendJson(response, 500, {error: error.message});
+		}
+	});
+	server.on('error', (error) => {
+		logger.log("HTTP control server failed:", error.message);
+	});
+	server.listen(address.port, address.host, () => {
+		logger.log(`HTTP control listening on ${address.host}:${address.port}`);
+	});
+	return server;
+}
+
+module.exports = {
+	startHttpControl,
+};
@@ -1,176 +1,340 @@
 const blessed = require('neo-blessed');
-const {Console} = require('console');
+const fs = require('fs');
+const readline = require('readline');
 
 const SnapcastClient = require('./snapcast-client');
+const ReadOnlySnapcastClient = require('./read-only-snapcast-client');
+const {createActions} = require('./actions');
+const {AppState} = require('./app');
+const {startHttpControl} = require('./http-control');
+const {writeConfigFile} = require('./config');
+const {STATE_FILE, createLogger, parseArgs, readSavedState, serverLabel} = require('./cli');
+const {clientName, findClient, groupName, serverDetails} = require('./groups');
+const {helpTable, loadConfig} = require('./keys');
+const {MixerView, SPINNER_INTERVAL, useUnicode} = require('./ui');
+const {formatVolume} = require('./volume');
 
-const logger = new Console(process.stderr, process.stderr);
+// Least milliseconds between redraws prompted by messages from the server,
+// so that a burst of them is drawn once
+const REDRAW_INTERVAL = 16;
+
+// Milliseconds for which the mixer is shown by --once before exiting
+const ONCE_PAUSE = 500;
+
+// Most milliseconds to wait on quitting for queued volumes to be sent
+const QUIT_FLUSH_TIME = 1000;
+
+const argv = parseArgs(process.argv.slice(2));
+const logger = createLogger(argv.logFile);
+
+function createClient() {
+	const client = argv.readOnly
+		? new ReadOnlySnapcastClient(argv.server[0])
+		: new SnapcastClient(argv.server[0]);
+	client.maxVolume = argv.maxVolume;
+	return client;
+}
+
+// A JSON record of a volume or mute change being sent, or null for other
+// messages
+function changeEvent(message) {
+	const params = message.params || {};
+	if (message.method === 'Client.SetVolume') {
+		const event = {client: params.id};
+		if (params.volume.percent != null) {
+			event.volume = params.volume.percent;
+		}
+		if (params.volume.muted != null) {
+			event.muted = params.volume.muted;
+		}
+		return event;
+	}
+	if (message.method === 'Group.SetMute') {
+		return {group: params.id, muted: params.mute};
+	}
+	return null;
+}
+
+// Write a line of JSON for each volume or mute change to the file, pipe or
+// stdout given with --emit-events
+function emitEvents(client) {
+	const stream = argv.emitEvents === '-'
+		? process.stdout
+		: fs.createWriteStream(argv.emitEvents, {flags: 'a'});
+	stream.on('error', (error) => {
+		logger.log("could not write events:", error.message);
+	});
+	client.on('sent', (message) => {
+		const event = changeEvent(message);
+		if (event) {
+			stream.write(JSON.stringify(event) + '\n');
+		}
+	});
+}
+
+async function connectClient() {
+	const client = createClient();
+	if (argv.emitEvents) {
+		emitEvents(client);
+	}
+	await client.connect();
+	return client;
+}
+
+async function setVolumeCommand() {
+	const client = await connectClient();
+	const response = await client.getStatus();
+	const target = findClient(response.server.groups, argv.client);
+	if (!target) {
+		console.error(`No client found matching "${argv.client}"`);
+		await client.close();
+		process.exitCode = 1;
+		return;
+	}
+	await client.setVolume(target.id, argv.volume);
+	console.log(`Set ${clientName(target)} to ${argv.volume}%`);
+	await client.close();
+}
+
+async function infoCommand() {
+	const client = await connectClient();
+	const response = await client.getStatus();
+	await client.close();
+
+	for (const [label, value] of serverDetails(response)) {
+		console.log(`${label}: ${value}`);
+	}
+}
+
+async function statusCommand() {
+	const client = await connectClient();
+	const response = await client.getStatus();
+	await client.close();
+
+	const groups = response.server.groups;
+	if (argv.json) {
+		const clients = [];
+		for (const group of groups) {
+			for (const member of group.clients) {
+				clients.push({
+					id: member.id,
+					name: clientName(member),
+					volume: member.config.volume.percent,
+					muted: member.config.volume.muted,
+					groupId: group.id,
+				});
+			}
+		}
+		console.log(JSON.stringify({
+			groups: groups.map((group) => ({
+				id: group.id,
+				name: group.name,
+				muted: group.muted,
+				streamId: group.stream_id,
+			})),
+			clients: clients,
+		}, null, '\t'));
+		return;
+	}
+
+	for (const group of groups) {
+		console.log(`${group.name.length ? group.name : group.id}${group.muted ? " (muted)" : ''}`);
+		for (const member of group.clients) {
+			console.log(`\t${clientName(member)}\t${formatVolume(member.config.volume.percent, argv.units)}${member.config.volume.muted ? " (muted)" : ''}`);
+		}
+	}
+}
 
 async function main() {
-	const client = new SnapcastClient({
-		// TODO: make this configurable
-		host: 'localhost',
-		port: 1705,
+	if (argv.emitEvents === '-') {
+		console.error("Events can't be written to stdout while the mixer is running; give a file or pipe instead");
+		process.exitCode = 1;
+		return;
+	}
+	if (argv.controlFifo && !(fs.existsSync(argv.controlFifo) && fs.statSync(argv.controlFifo).isFIFO())) {
+		console.error(`${argv.controlFifo} is not a named pipe; create one with mkfifo`);
+		process.exitCode = 1;
+		return;
+	}
+
+	const {bindings, theme} = loadConfig(argv);
+	const savedState = readSavedState(argv);
+	const client = await connectClient();
+	logger.log("connected");
+	client.on('sent', (message) => {
+		logger.log("sent:", message.method, JSON.stringify(message.params || {}));
 	});
+
+	const state = new AppState(client, argv, logger);
+	state.loadGroupSettings(serverLabel(client.options));
+
+	// Timer for a redraw waiting to happen, and when the last one began
+	let redrawTimer = null;
+	let lastRedrawAt = 0;
+
+	// Redraw soon, at most once per interval; the redraw fetches the latest
+	// state, so whatever arrives while it's waiting is included
+	function requestRedraw() {
+		if (redrawTimer) {
+			return;
+		}
+		redrawTimer = setTimeout(async () => {
+			redrawTimer = null;
+			lastRedrawAt = Date.now();
+			await updateDisplay();
+		}, Math.max(0, lastRedrawAt + REDRAW_INTERVAL - Date.now()));
+	}
+
 	client.on('message', (message) => {
 		// TODO: handle notifications in a more sophisticated way
 		logger.log("got a message:", message);
-		updateDisplay();
+		requestRedraw();
 	});
-	await client.connect();
 
 	const screen = blessed.screen({
 		smartCSR: true,
 		dockBorders: true,
+		forceUnicode: useUnicode(argv),
 	});
 	screen.title = "Snapmixer";
 
-	const form = blessed.form({
-		position: {
-			left: 0,
-			top: 0,
-			width: '100%',
-			height: '100%',
-		},
-		scrollable: true,
-		scrollbar: {
-			style: {
-				bg: '#999',
-			},
-			track: {
-				bg: '#333',
-			},
-		},
-		keys: true,
-		vi: true,
-		mouse: true,
+	// On a crash, restore the terminal before reporting the error, so that
+	// the shell isn't left in the alternate screen with mouse reporting on
+	process.on('uncaughtException', (error) => {
+		screen.destroy();
+		console.error(error);
+		process.exit(1);
 	});
-	screen.append(form);
 
-	const groupBoxes = {};
+	const view = new MixerView(screen, client, state, argv, theme, bindings, logger);
+	view.on('redraw', requestRedraw);
 
-	async function updateDisplay() {
-		const response = await client.getStatus();
-		const groups = response.server.groups;
-		let formY = 0;
-		for (const group of groups) {
-			let groupY = 0;
-			let groupSpec = groupBoxes[group.id];
-			if (!groupSpec) {
-				groupSpec = groupBoxes[group.id] = {
-					box: blessed.box({
-						position: {
-							left: 0,
-							top: formY,
-							width: '100%-1',
-							// height: set later
-						},
-						border: 'line',
-						style: {
-							border: {
-								fg: '#333',
-							},
-							label: {
-								fg: '#666',
-								bold: true,
-								position: {
-									left: 16,
-								},
-								left: 16,
-								rleft: 16,
-							},
-						},
-						tags: true,
-						// label: set later
-					}),
-					clients: {},
-				};
-				form.append(groupSpec.box);
-			}
+	// Index of the server in use, out of those given with --server
+	let serverIndex = 0;
 
-			if (group.muted || group.name.length) {
-				groupSpec.box.setLabel(` ${group.name}${group.muted ? `${group.name.length ? ' ' : ''}{red-fg}(muted){/}` : ''} `);
-			} else {
-				groupSpec.box.removeLabel();
-			}
+	// Show which server is in use, if there's a choice, and which client to
+	// focus first on it: the one asked for, or else the one selected when
+	// it was last used
+	function showServer() {
+		view.serverName = argv.server.length > 1 ? serverLabel(argv.server[serverIndex]) : null;
+		view.initialFocus = argv.focus != null ? argv.focus
+			: savedState.server === serverLabel(client.options) ? savedState.focus
+			: null;
+		view.initiallyFocused = false;
+	}
+	showServer();
 
-			for (const client of group.clients) {
-				let clientSpec = groupSpec.clients[client.id];
-				if (!clientSpec) {
-					clientSpec = groupSpec.clients[client.id] = {
-						label: blessed.text({
-							position: {
-								left: 0,
-								top: groupY,
-								width: 16,
-								height: 1,
-							},
-							style: {
-								// fg: set later
-							},
-							// content: set later
-						}),
-						muteStatus: blessed.text({
-							position: {
-								left: 17,
-								top: groupY,
-								width: 1,
-								height: 1,
-							},
-							style: {
-								fg: 'red',
-								bold: true,
-							},
-							// content: set later
-						}),
-						bar: blessed.progressbar({
-							pch: '\u2591',
-							style: {
-								bg: '#333',
-								bar: {
-									bg: '#666',
-									fg: '#ccc',
-								},
-								focus: {
-									bg: 'blue',
-									bar: {
-										bg: 'lightblue',
-										fg: 'white',
-									},
-								},
-							},
-							position: {
-								width: `100%-${19 + 2 + 1}`,
-								height: 1,
-								top: groupY,
-								left: 19,
-							},
-							// filled: set later
-							input: true,
-						}),
-					};
-					groupSpec.box.append(clientSpec.label);
-					groupSpec.box.append(clientSpec.muteStatus);
-					groupSpec.box.append(clientSpec.bar);
-
-					// Store the client and group IDs on the progress bar
-					// for easy access
-					clientSpec.bar.clientId = client.id;
-					clientSpec.bar.groupId = group.id;
-				}
-				clientSpec.label.setContent(client.config.name.length ? client.config.name : client.host.name);
-				clientSpec.label.style.fg = client.config.name.length ? 'white' : '#999';
-				clientSpec.muteStatus.setContent(client.config.volume.muted ? "M" : "");
-				clientSpec.bar.setProgress(client.config.volume.percent);
-				groupY += 2;
-			}
+	screen.on('resize', async () => {
+		view.checkSize();
+		view.updateFooter();
+		screen.render();
+		if (argv.columns !== 1) {
+			await updateDisplay();
+		}
+	});
+	view.checkSize();
+
+	// Show volumes as soon as they're queued, and the server's again once
+	// they've been sent
+	state.on('volume', (clientId, percent) => view.showVolume(clientId, percent));
+	state.on('sent', updateDisplay);
+	state.on('sendFailed', (clientId, error) => {
+		const clientSpec = view.clientSpecs[clientId];
+		reportError(`Setting the volume of ${clientSpec ? clientSpec.bar.clientName : clientId}`, error);
+	});
+	state.on('limit', () => view.hitLimit());
 
-			groupSpec.box.position.height = groupY + 1; // Group's bottom border
-			formY += groupSpec.box.position.height;
+	// Progress bar currently being dragged with the mouse, if any
+	let draggedBar = null;
+
+	// Set a client's volume according to a mouse position along its bar
+	async function setVolumeFromMouse(bar, x) {
+		if (!bar.lpos) {
+			return;
+		}
+		// The bar's full length is --max-volume
+		const width = bar.lpos.xl - bar.lpos.xi;
+		const fraction = (x - bar.lpos.xi) / Math.max(1, width - 1);
+		const percent = Math.round(Math.min(1, Math.max(0, fraction)) * argv.maxVolume);
+		if (percent === bar.draggedVolume) {
+			return;
 		}
+		bar.draggedVolume = percent;
+		state.applyVolume(bar.clientId, percent);
+	}
 
-		// TODO: handle clients and groups getting removed
+	screen.on('mouse', async (data) => {
+		if (!draggedBar) {
+			return;
+		}
+		if (data.action === 'mouseup') {
+			// The whole drag is undone at once
+			if (draggedBar.undoEntry) {
+				state.pushUndo(draggedBar.undoEntry).catch((error) => reportError("Recording the volume to undo", error));
+				draggedBar.undoEntry = null;
+			}
+			draggedBar = null;
+			await updateDisplay();
+		} else if (data.action === 'mousemove') {
+			setVolumeFromMouse(draggedBar, data.x);
+		}
+	});
 
+	// Mouse: click a row to focus it, click or drag along the bar to set the
+	// volume, scroll over it to adjust, and click a collapsed group to expand
+	// it, unless the help box or a dialog is in the way
+	view.on('select', (bar) => {
+		if (!helpMessage.hidden || screen.grabKeys) {
+			return;
+		}
+		view.focusBar(bar);
 		screen.render();
+	});
+	view.on('scroll', async (bar, steps) => {
+		if (!helpMessage.hidden || screen.grabKeys) {
+			return;
+		}
+		try {
+			await state.withUndo(state.linkedClientIds(bar.clientId), [], () => state.adjustVolume(bar.clientId, steps));
+			await updateDisplay();
+		} catch (error) {
+			reportError("Scrolling the volume", error);
+		}
+	});
+	view.on('drag', async (bar, data) => {
+		if (!helpMessage.hidden || screen.grabKeys) {
+			return;
+		}
+		draggedBar = bar;
+		draggedBar.draggedVolume = null;
+		try {
+			bar.undoEntry = await state.undoState(state.linkedClientIds(bar.clientId));
+			await setVolumeFromMouse(bar, data.x);
+		} catch (error) {
+			reportError("Setting the volume with the mouse", error);
+		}
+	});
+	view.on('expand', async (groupId) => {
+		if (!helpMessage.hidden || screen.grabKeys) {
+			return;
+		}
+		state.collapsedGroups.delete(groupId);
+		state.saveGroupSettings();
+		await updateDisplay();
+	});
+
+	// Redraw everything from the server's latest state, reporting rather
+	// than throwing any error, so callers needn't handle it
+	async function updateDisplay() {
+		if (!client.connected) {
+			// Redrawn on reconnecting
+			return;
+		}
+		try {
+			await view.draw();
+		} catch (error) {
+			reportError("Updating the display", error);
+		}
 	}
 
 	const helpMessage = blessed.message({
@@ -186,18 +350,18 @@ async function main() {
 		scrollable: true,
 		style: {
 			border: {
-				fg: '#333',
+				fg: theme.border,
 			},
 			label: {
-				fg: '#666',
+				fg: theme.label,
 			},
 		},
 		scrollbar: {
 			style: {
-				bg: '#999',
+				bg: theme.scrollbar,
 			},
 			track: {
-				bg: '#333',
+				bg: theme.track,
 			},
 		},
 		keys: true,
@@ -208,131 +372,596 @@ async function main() {
 	helpMessage.append(blessed.table({
 		transparent: true,
 		tags: true,
-		data: [
-			["{bold}?{/bold}, {bold}F1{/bold}", "Toggle this help box"],
-			["{bold}down{/bold}, {bold}up{/bold}", "Select mixer, scroll help"],
-			["{bold}j{/bold}, {bold}k{/bold}", "Select mixer, scroll help"],
-			["{bold}tab{/bold}, {bold}shift-tab{/bold}", "Select mixer"],
-			["{bold}left{/bold}, {bold}right{/bold}", "Adjust volume"],
-			["{bold}h{/bold}, {bold}l{/bold}", "Adjust volume"],
-			["{bold}shift-left{/bold}, {bold}shift-right{/bold}", "Adjust volume in large increments"],
-			["{bold}H{/bold}, {bold}L{/bold}", "Adjust volume in large increments"],
-			["{bold}1{/bold}, {bold}2{/bold}, {bold}3{/bold}, ..., {bold}0{/bold}", "Set volume to 10%, 20%, 30%, ..., 100%"],
-			["{bold}m{/bold}", "Toggle client mute"],
-			["{bold}g{/bold}", "Toggle group mute"],
-			["{bold}esc{/bold}, {bold}q{/bold}, {bold}control-c{/bold}", "Quit"],
-		],
+		data: helpTable(bindings, argv),
 		position: {
 			width: '100%-3' /* left and right border, plus scrollbar */,
 		},
 	}));
 
-	// Quit
-	screen.key(['escape', 'q', 'C-c'], (ch, key) => {
-		client.close();
-		process.exit(0);
+	const inputPrompt = blessed.prompt({
+		hidden: true,
+		position: {
+			width: 40,
+			height: 'shrink',
+			left: 'center',
+			top: 'center',
+		},
+		border: 'line',
+		style: {
+			border: {
+				fg: theme.border,
+			},
+			label: {
+				fg: theme.label,
+			},
+		},
+		keys: true,
+		tags: true,
 	});
+	screen.append(inputPrompt);
 
-	// Help
-	screen.key(['?', 'f1'], (ch, key) => {
-		helpMessage.toggle();
-		if (!helpMessage.hidden) {
-			helpMessage.focus();
-			helpMessage.resetScroll();
-		}
-		screen.render();
+	// Ask for a line of text; resolves to null if cancelled
+	function readInput(label, text, value = '') {
+		return new Promise((resolve, reject) => {
+			inputPrompt.setLabel(` ${label} `);
+			inputPrompt.input(text, value, (error, result) => {
+				resolve(error ? null : result);
+			});
+		});
+	}
+
+	// Ask for a choice from a list; resolves to the chosen index, or null if
+	// cancelled
+	function chooseFromList(label, items) {
+		return new Promise((resolve, reject) => {
+			const list = blessed.list({
+				position: {
+					width: Math.min(screen.width, 40),
+					height: Math.min(screen.height, items.length + 2),
+					left: 'center',
+					top: 'center',
+				},
+				border: 'line',
+				label: ` ${label} `,
+				style: {
+					border: {
+						fg: theme.border,
+					},
+					label: {
+						fg: theme.label,
+					},
+					selected: {
+						bg: theme.focusTrack,
+						inverse: view.monochrome,
+					},
+				},
+				items: items,
+				keys: true,
+				vi: true,
+				mouse: true,
+			});
+			screen.append(list);
+			screen.saveFocus();
+			list.focus();
+
+			// Keep the global key bindings from firing while choosing
+			screen.grabKeys = true;
+
+			const done = (index) => {
+				screen.grabKeys = false;
+				list.destroy();
+				screen.restoreFocus();
+				screen.render();
+				resolve(index);
+			};
+			list.once('select', (item, index) => done(index));
+			list.once('cancel', () => done(null));
+			screen.render();
+		});
+	}
+
+	// Show a message until it's dismissed with escape, enter or q; resolves to
+	// the name of the key which dismissed it
+	function showMessage(label, text) {
+		return new Promise((resolve, reject) => {
+			const box = blessed.box({
+				position: {
+					width: Math.min(screen.width, 60),
+					height: 'shrink',
+					left: 'center',
+					top: 'center',
+				},
+				border: 'line',
+				label: ` ${label} `,
+				content: text,
+				padding: {
+					left: 1,
+					right: 1,
+				},
+				style: {
+					border: {
+						fg: theme.border,
+					},
+					label: {
+						fg: theme.label,
+					},
+				},
+				tags: true,
+			});
+			screen.append(box);
+			screen.saveFocus();
+			box.focus();
+
+			// Keep the global key bindings from firing while it's shown
+			screen.grabKeys = true;
+
+			box.on('keypress', (ch, key) => {
+				if (!['escape', 'enter', 'q'].includes(key.name)) {
+					return;
+				}
+				screen.grabKeys = false;
+				box.destroy();
+				screen.restoreFocus();
+				screen.render();
+				resolve(key.name);
+			});
+			screen.render();
+		});
+	}
+
+	function showNotice(text) {
+		return showMessage("Note", text);
+	}
+
+	// Log and show a command which failed
+	function reportError(description, error) {
+		const text = error instanceof Error ? error.message : String(error);
+		logger.log(`${description} failed:`, text);
+		showMessage("Error", `${blessed.escape(description)} failed: ${blessed.escape(text)}`);
+	}
+
+	const quitMessage = blessed.message({
+		hidden: true,
+		position: {
+			width: 'shrink',
+			height: 'shrink',
+			left: 'center',
+			top: 'center',
+		},
+		border: 'line',
+		padding: {
+			left: 1,
+			right: 1,
+		},
+		style: {
+			border: {
+				fg: theme.border,
+			},
+		},
+	});
+	screen.append(quitMessage);
+
+	// Briefly shows a short message, such as the master level after it's
+	// adjusted
+	const flashMessage = blessed.message({
+		hidden: true,
+		position: {
+			width: 'shrink',
+			height: 'shrink',
+			left: 'center',
+			top: 'center',
+		},
+		border: 'line',
+		padding: {
+			left: 1,
+			right: 1,
+		},
+		style: {
+			border: {
+				fg: theme.border,
+			},
+		},
+	});
+	screen.append(flashMessage);
+
+	const connectionMessage = blessed.box({
+		hidden: true,
+		position: {
+			width: 'shrink',
+			height: 'shrink',
+			left: 'center',
+			top: 'center',
+		},
+		border: 'line',
+		label: " Disconnected ",
+		padding: {
+			left: 1,
+			right: 1,
+		},
+		style: {
+			border: {
+				fg: theme.border,
+			},
+			label: {
+				fg: theme.label,
+			},
+		},
 	});
+	screen.append(connectionMessage);
+
+	// Failed attempts to reconnect since the connection was lost
+	let reconnectAttempts = 0;
+
+	// Timer for the next scheduled attempt to reconnect
+	let reconnectTimer = null;
+
+	// Whether an attempt to reconnect is in progress
+	let reconnecting = false;
 
-	// Adjust volume in small increments
-	screen.key(['right', 'l'], async (ch, key) => {
-		if (!helpMessage.hidden) {
+	// Wait before the next attempt to reconnect, backing off exponentially
+	function scheduleReconnect() {
+		if (argv.maxReconnectAttempts && reconnectAttempts >= argv.maxReconnectAttempts) {
+			giveUpReconnecting();
 			return;
 		}
-		const widget = screen.focused;
-		if (widget.type !== 'progress-bar') {
+		const delay = Math.min(argv.reconnectMaxDelay, Math.pow(2, reconnectAttempts));
+		connectionMessage.setContent(reconnectAttempts
+			? `Reconnect attempt ${reconnectAttempts} failed; retrying in ${delay}s`
+			: `Connection lost; reconnecting in ${delay}s`);
+		connectionMessage.show();
+		connectionMessage.setFront();
+		screen.render();
+		reconnectTimer = setTimeout(reconnect, delay * 1000);
+	}
+
+	async function reconnect() {
+		reconnectTimer = null;
+		reconnecting = true;
+		try {
+			await client.connect();
+		} catch (error) {
+			reconnecting = false;
+			reconnectAttempts++;
+			logger.log(`reconnect attempt ${reconnectAttempts} failed:`, error.message);
+			scheduleReconnect();
 			return;
 		}
-		await client.adjustVolume(widget.clientId, 1);
-		updateDisplay();
-	});
-	screen.key(['left', 'h'], async (ch, key) => {
-		if (!helpMessage.hidden) {
-			return;
+		reconnecting = false;
+		logger.log("reconnected");
+		reconnectAttempts = 0;
+		connectionMessage.hide();
+		await updateDisplay();
+	}
+
+	// Disconnect and connect to another server, forgetting everything about
+	// the last one
+	async function switchServer(index) {
+		serverIndex = index;
+		clearTimeout(reconnectTimer);
+		reconnectTimer = null;
+		if (client.connected) {
+			client.destroy();
 		}
-		const widget = screen.focused;
-		if (widget.type !== 'progress-bar') {
+		client.options = argv.server[index];
+		screen.title = `Snapmixer: ${serverLabel(client.options)}`;
+		state.reset();
+		state.loadGroupSettings(serverLabel(client.options));
+		showServer();
+
+		reconnectAttempts = 0;
+		connectionMessage.setContent(`Connecting to ${serverLabel(client.options)}`);
+		connectionMessage.show();
+		connectionMessage.setFront();
+		screen.render();
+		await reconnect();
+	}
+
+	async function giveUpReconnecting() {
+		connectionMessage.hide();
+		const key = await showMessage("Disconnected", `Gave up after ${reconnectAttempts} attempts to reconnect.\nPress enter to try again, or q to quit.`);
+		if (key === 'q') {
+			quit();
 			return;
 		}
-		await client.adjustVolume(widget.clientId, -1);
-		updateDisplay();
-	});
+		reconnectAttempts = 0;
+		reconnect();
+	}
 
-	// Adjust volume in large increments
-	screen.key(['S-right', 'S-l'], async (ch, key) => {
-		if (!helpMessage.hidden) {
-			return;
+	// Whether the server is being checked after a quiet spell
+	let checkingConnection = false;
+
+	// Ask the server for its status, and reconnect if it doesn't answer in
+	// time
+	async function checkConnection() {
+		checkingConnection = true;
+		const staleTimer = setTimeout(() => {
+			logger.log("connection stale");
+			client.destroy();
+			scheduleReconnect();
+		}, argv.responseTimeout);
+		try {
+			await client.getStatus();
+		} catch (error) {
+			// The connection was lost or dropped as stale
+		}
+		clearTimeout(staleTimer);
+		checkingConnection = false;
+	}
+
+	// If the server has been quiet for a while, check it's still there
+	setInterval(async () => {
+		// Keep the time since the last update in the footer current
+		if (argv.footer) {
+			view.updateFooter();
+			screen.render();
 		}
-		const widget = screen.focused;
-		if (widget.type !== 'progress-bar') {
+
+		if (!client.connected || checkingConnection || Date.now() - client.lastReceived < argv.quietTimeout) {
 			return;
 		}
-		await client.adjustVolume(widget.clientId, 3);
-		updateDisplay();
+		await checkConnection();
+	}, 1000);
+
+	// Spin the activity spinner while anything is waiting to be sent or
+	// answered, redrawing only while it's showing
+	setInterval(() => {
+		view.spin(client.hasPendingRequests() || state.hasPendingVolumes());
+	}, SPINNER_INTERVAL);
+
+	client.on('disconnect', () => {
+		logger.log("connection lost");
+		scheduleReconnect();
 	});
-	screen.key(['S-left', 'S-h'], async (ch, key) => {
-		if (!helpMessage.hidden) {
+
+	// Fetch the server's state now, rather than waiting for a change or a
+	// quiet spell
+	async function refresh() {
+		if (!client.connected || checkingConnection) {
 			return;
 		}
-		const widget = screen.focused;
-		if (widget.type !== 'progress-bar') {
+		flashMessage.display("Refreshing", 1, () => {});
+		await checkConnection();
+		await updateDisplay();
+	}
+
+	// Switch to the next server given with --server
+	async function nextServer() {
+		if (reconnecting || argv.server.length < 2) {
 			return;
 		}
-		await client.adjustVolume(widget.clientId, -3);
-		updateDisplay();
-	});
-
-	// Snap volume to 10%, 20%, 30%, ..., 100%
-	for (let i = 0; i < 10; i++) {
-		screen.key([i.toString()], async (ch, key) => {
-			if (!helpMessage.hidden) {
-				return;
-			}
-			const widget = screen.focused;
-			if (widget.type !== 'progress-bar') {
-				return;
-			}
-			await client.setVolume(widget.clientId, (i === 0 ? 10 : i) * 10);
-			updateDisplay();
-		});
+		await switchServer((serverIndex + 1) % argv.server.length);
 	}
 
-	// Toggle client mute
-	screen.key(['m'], async (ch, key) => {
-		if (!helpMessage.hidden) {
+	// Drop the connection, if any, and reconnect immediately
+	async function reconnectNow() {
+		if (reconnecting) {
 			return;
 		}
-		const widget = screen.focused;
-		if (widget.type !== 'progress-bar') {
+		clearTimeout(reconnectTimer);
+		reconnectTimer = null;
+		if (client.connected) {
+			client.destroy();
+		}
+		reconnectAttempts = 0;
+		connectionMessage.setContent("Reconnecting");
+		connectionMessage.show();
+		connectionMessage.setFront();
+		screen.render();
+		await reconnect();
+	}
+
+	// A count typed with alt and the digit keys repeats the next movement or
+	// volume adjustment; any other key consumes it
+	let pendingCount = '';
+	let count = 1;
+	screen.on('keypress', (ch, key) => {
+		if (key.meta && /^[0-9]$/.test(key.name)) {
+			pendingCount += key.name;
 			return;
 		}
-		await client.toggleClientMute(widget.clientId);
-		updateDisplay();
+		count = pendingCount.length ? Math.max(1, parseInt(pendingCount, 10)) : 1;
+		pendingCount = '';
 	});
 
-	// Toggle group mute
-	screen.key(['g'], async (ch, key) => {
-		if (!helpMessage.hidden) {
+	// Remember the server and selected client for next time, as long as the
+	// server is connected
+	function saveState() {
+		if (!argv.saveState || !client.connected) {
 			return;
 		}
-		const widget = screen.focused;
-		if (widget.type !== 'progress-bar') {
+		const focused = screen.focused;
+		try {
+			writeConfigFile(STATE_FILE, {
+				server: serverLabel(client.options),
+				focus: focused && focused.type === 'progress-bar' ? focused.clientId : null,
+			});
+		} catch (error) {
+			logger.log("could not save state:", error.message);
+		}
+	}
+
+	// Whether quitting has begun, so that cleaning up happens only once
+	let quitting = false;
+
+	async function quit() {
+		if (quitting) {
 			return;
 		}
-		await client.toggleGroupMute(widget.groupId);
-		updateDisplay();
+		quitting = true;
+		saveState();
+
+		// Don't lose the last changes made, but don't hang on a server
+		// which isn't answering either
+		await Promise.race([
+			state.flushVolumes(),
+			new Promise((resolve) => setTimeout(resolve, QUIT_FLUSH_TIME)),
+		]);
+		client.close();
+
+		// Restore the terminal, including turning off mouse reporting
+		screen.destroy();
+		process.exit(0);
+	}
+
+	// Quit cleanly when a signal asks us to, such as from a service manager
+	for (const signal of ['SIGTERM', 'SIGINT', 'SIGHUP']) {
+		process.on(signal, quit);
+	}
+
+	// Handlers for each action, bound to keys below
+	const actions = createActions({
+		argv: argv,
+		client: client,
+		logger: logger,
+		screen: screen,
+		state: state,
+		theme: theme,
+		view: view,
+		helpMessage: helpMessage,
+		quitMessage: quitMessage,
+		flashMessage: flashMessage,
+		chooseFromList: chooseFromList,
+		readInput: readInput,
+		showMessage: showMessage,
+		showNotice: showNotice,
+		get count() {
+			return count;
+		},
+		updateDisplay: updateDisplay,
+		refresh: refresh,
+		nextServer: nextServer,
+		reconnect: reconnectNow,
+		quit: quit,
 	});
 
-	updateDisplay();
+	// Carry out a command read from the control pipe
+	async function runControlCommand(line) {
+		const [command, target, value] = line.trim().split(/\s+/);
+		const groups = (await client.getStatus()).server.groups;
+		if (command === 'vol') {
+			const snapClient = findClient(groups, target);
+			if (!snapClient || !/^\d+$/.test(value) || parseInt(value, 10) > argv.maxVolume) {
+				throw new Error(`expected vol <client> <0-${argv.maxVolume}>`);
+			}
+			state.queueVolume(snapClient.id, parseInt(value, 10));
+		} else if (command === 'mute') {
+			const group = groups.find((candidate) => candidate.id === target)
+				|| groups.find((candidate) => groupName(candidate) === target);
+			if (!group || (value != null && !['on', 'off'].includes(value))) {
+				throw new Error("expected mute <group> [on|off]");
+			}
+			await client.setGroupMute(group.id, value == null ? !group.muted : value === 'on');
+			await updateDisplay();
+		} else if (command) {
+			throw new Error(`unknown command ${command}`);
+		}
+	}
+
+	// Read commands from the control pipe, opening it again each time a
+	// writer closes it
+	function listenForCommands(path) {
+		const input = fs.createReadStream(path);
+		input.on('error', (error) => {
+			logger.log("could not read control pipe:", error.message);
+		});
+		const lines = readline.createInterface({
+			input: input,
+		});
+		lines.on('line', async (line) => {
+			try {
+				await runControlCommand(line);
+			} catch (error) {
+				logger.log(`control command "${line}" failed:`, error.message);
+			}
+		});
+		lines.on('close', () => listenForCommands(path));
+	}
+	if (argv.controlFifo) {
+		listenForCommands(argv.controlFifo);
+	}
+
+	// Serve the HTTP interface, with volumes set through the same queue as
+	// the keys so that changes from both are sent in order
+	if (argv.httpListen) {
+		startHttpControl(argv.httpListen, {
+			maxVolume: argv.maxVolume,
+			listClients: async () => {
+				const clients = [];
+				for (const group of (await client.getStatus()).server.groups) {
+					for (const member of group.clients) {
+						clients.push({
+							id: member.id,
+							name: clientName(member),
+							volume: state.displayedVolume(member),
+							muted: member.config.volume.muted,
+							connected: member.connected,
+							groupId: group.id,
+						});
+					}
+				}
+				return clients;
+			},
+			setVolume: async (idOrName, volume) => {
+				const snapClient = findClient((await client.getStatus()).server.groups, idOrName);
+				if (!snapClient) {
+					return null;
+				}
+				state.queueVolume(snapClient.id, volume);
+				return {id: snapClient.id, volume: volume};
+			},
+		}, logger);
+	}
+
+	// Bind each action's keys
+	const actionKeys = {};
+	for (const key of Object.keys(bindings)) {
+		const action = bindings[key];
+		actionKeys[action] = (actionKeys[action] || []).concat(key);
+	}
+	for (const action of Object.keys(actionKeys)) {
+		const [name, argument] = action.split(':');
+		screen.key(actionKeys[action], async (ch, key) => {
+			try {
+				await actions[name](ch, key, argument);
+			} catch (error) {
+				reportError(`The ${action} action`, error);
+			}
+		});
+	}
+
+	// Draw once and exit, restoring the terminal before writing out what was
+	// drawn
+	if (argv.once || argv.dumpUi) {
+		await updateDisplay();
+		setTimeout(() => {
+			const text = screen.lines
+				.map((line) => line.map((cell) => cell[1]).join('').replace(/\s+$/, ''))
+				.join('\n');
+			client.close();
+			screen.destroy();
+			if (argv.dumpUi) {
+				process.stdout.write(`${text}\n`);
+			}
+			process.exit(0);
+		}, ONCE_PAUSE);
+		return;
+	}
+
+	await updateDisplay();
 }
 
-main();
+// Report a subcommand which failed, such as on not being able to connect
+function commandFailed(error) {
+	console.error(error.message);
+	process.exitCode = 1;
+}
+
+if (argv._[0] === 'set') {
+	setVolumeCommand().catch(commandFailed);
+} else if (argv._[0] === 'status') {
+	statusCommand().catch(commandFailed);
+} else if (argv._[0] === 'info') {
+	infoCommand().catch(commandFailed);
+} else {
+	main();
+}
@@ -14,33 +14,47 @@ class JsonRpcClient extends events.EventEmitter {
 	}
 
 	async connect() {
+		this.closing = false;
+		this.connected = false;
+		this.buffer = '';
+		this.responseTime = null;
 		return new Promise((resolve, reject) => {
-			this.client = net.createConnection(this.options, () => {
+			const socket = this.client = net.createConnection(this.options, () => {
 				this.connected = true;
 				this.connectionOk = true;
-				this.client.on('error', (error) => {
+				this.lastReceived = Date.now();
+				socket.on('error', (error) => {
+					// A close event follows
 					this.connectionOk = false;
-					throw new Error(error);
 				});
-				this.client.on('close', () => {
+				socket.on('close', () => {
+					// Ignore a socket which has since been replaced
+					if (socket !== this.client) {
+						return;
+					}
 					this.connected = false;
 					this.connectionOk = false;
+					this.rejectPending();
+
+					if (!this.closing) {
+						this.emit('disconnect');
+					}
 				});
 				resolve();
 			});
 
-			this.client.once('error', (error) => {
+			socket.once('error', (error) => {
 				this.connectionOk = false;
 				if (!this.connected) {
 					reject(error);
-				} else {
-					throw error;
 				}
 			});
 
-			this.client.setEncoding('utf8');
+			socket.setEncoding('utf8');
+
+			socket.on('data', (data) => {
+				this.lastReceived = Date.now();
 
-			this.client.on('data', (data) => {
 				// Append to existing buffer
 				this.buffer += data;
 
@@ -55,9 +69,15 @@ class JsonRpcClient extends events.EventEmitter {
 				// Deal with messages
 				for (const message of objects) {
 					if (message.id && this.promiseResolvers[message.id]) {
-						// This is a response we expected;
-						// resolve the corresponding promise
-						this.promiseResolvers[message.id](message);
+						// This is a response we expected; resolve the
+						// corresponding promise, or reject it if the
+						// server reported an error
+						this.recordResponseTime(Date.now() - this.promiseResolvers[message.id].sentAt);
+						if (message.error) {
+							this.promiseResolvers[message.id].reject(new Error(message.error.message || JSON.stringify(message.error)));
+						} else {
+							this.promiseResolvers[message.id].resolve(message);
+						}
 						delete this.promiseResolvers[message.id];
 					} else {
 						// This is an unexpected message; emit an event
@@ -69,6 +89,7 @@ class JsonRpcClient extends events.EventEmitter {
 	}
 
 	async close() {
+		this.closing = true;
 		return new Promise((resolve, reject) => {
 			this.client.end(() => {
 				this.client.destroy();
@@ -77,6 +98,38 @@ class JsonRpcClient extends events.EventEmitter {
 		});
 	}
 
+	// Drop the connection immediately, without emitting a disconnect event;
+	// the socket's close event may come after another connection has
+	// replaced it, so requests awaiting a response are rejected now
+	destroy() {
+		this.closing = true;
+		this.connected = false;
+		this.connectionOk = false;
+		this.client.destroy();
+		this.rejectPending();
+	}
+
+	// Reject requests still awaiting a response, as nothing more is coming
+	// for them
+	rejectPending() {
+		const pending = this.promiseResolvers;
+		this.promiseResolvers = {};
+		for (const id of Object.keys(pending)) {
+			pending[id].reject(new Error("Connection closed"));
+		}
+	}
+
+	// Keep a rolling average of milliseconds between sending requests and
+	// their responses arriving, which starts again on connecting
+	recordResponseTime(time) {
+		this.responseTime = this.responseTime == null ? time : this.responseTime * 0.8 + time * 0.2;
+	}
+
+	// Whether any requests are still awaiting a response
+	hasPendingRequests() {
+		return Object.keys(this.promiseResolvers).length > 0;
+	}
+
 	async send(method, params, notification = false) {
 		return new Promise((resolve, reject) => {
 			if (!this.connectionOk) {
@@ -95,10 +148,15 @@ class JsonRpcClient extends events.EventEmitter {
 
 			if (!notification) {
 				message.id = uuidv4();
-				this.promiseResolvers[message.id] = resolve;
+				this.promiseResolvers[message.id] = {
+					resolve: resolve,
+					reject: reject,
+					sentAt: Date.now(),
+				};
 			}
 
 			this.client.write(JSON.stringify(message) + "\r\n");
+			this.emit('sent', message);
 
 			if (notification) {
 				resolve();
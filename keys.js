@@ -0,0 +1,237 @@
+const blessed = require('neo-blessed');
+
+const {readJsonFile} = require('./config');
+const {SORT_MODES} = require('./groups');
+const {DEFAULT_THEME, validateTheme} = require('./theme');
+const {isMonochrome} = require('./ui');
+const {snapTargets} = require('./volume');
+
+// Latency adjustment per keypress, in milliseconds
+const LATENCY_STEP = 10;
+
+// Default key bindings, from blessed key names to action names, some of which
+// take an argument after a colon
+const DEFAULT_BINDINGS = {
+	'escape': 'quit',
+	'q': 'quit',
+	'C-c': 'quit',
+	'?': 'help',
+	'f1': 'help',
+	'down': 'next',
+	'j': 'next',
+	'tab': 'next',
+	'up': 'previous',
+	'k': 'previous',
+	'S-tab': 'previous',
+	'S-down': 'nextGroup',
+	'S-j': 'nextGroup',
+	'S-up': 'previousGroup',
+	'S-k': 'previousGroup',
+	'f': 'jump',
+	'right': 'volumeUp',
+	'l': 'volumeUp',
+	'left': 'volumeDown',
+	'h': 'volumeDown',
+	'S-right': 'volumeUpLarge',
+	'S-l': 'volumeUpLarge',
+	'S-left': 'volumeDownLarge',
+	'S-h': 'volumeDownLarge',
+	'>': 'volumeUpFine',
+	'<': 'volumeDownFine',
+	'+': 'masterUp',
+	'-': 'masterDown',
+	'S-n': 'normalize',
+	'1': 'snap:1',
+	'2': 'snap:2',
+	'3': 'snap:3',
+	'4': 'snap:4',
+	'5': 'snap:5',
+	'6': 'snap:6',
+	'7': 'snap:7',
+	'8': 'snap:8',
+	'9': 'snap:9',
+	'0': 'snap:10',
+	':': 'typeVolume',
+	'=': 'typeVolume',
+	'm': 'toggleMute',
+	'g': 'toggleGroupMute',
+	'S-m': 'toggleGroupMembersMute',
+	'!': 'muteAll',
+	']': 'latencyUp',
+	'[': 'latencyDown',
+	'e': 'syncLatency',
+	'S-r': 'rename',
+	'S-g': 'moveToGroup',
+	'd': 'deleteClient',
+	'enter': 'chooseStream',
+	'i': 'streamInfo',
+	'S-p': 'playPause',
+	'}': 'nextTrack',
+	'{': 'previousTrack',
+	'o': 'serverInfo',
+	's': 'solo',
+	'u': 'undo',
+	'/': 'filter',
+	'S-s': 'saveSnapshot',
+	'r': 'restoreSnapshot',
+	'f5': 'refresh',
+	'C-r': 'reconnect',
+	'c': 'nextServer',
+	'y': 'copyId',
+	'a': 'toggleIp',
+	'v': 'toggleHideMuted',
+	'S-o': 'cycleSort',
+	'p': 'pin',
+	'b': 'link',
+	'%': 'relative',
+	'z': 'collapse',
+	'S-z': 'expandAll',
+};
+
+// Rows of the help box, each describing one or more actions, whose keys are
+// looked up in the bindings; some descriptions depend on the options
+function helpRows(options) {
+	const targets = snapTargets(options.maxVolume, options.snapSteps);
+	return [
+		[['help'], "Toggle this help box"],
+		[['next', 'previous'], "Select mixer, scroll help"],
+		[['nextGroup', 'previousGroup'], options.bigJump === 'group' ? "Select the first mixer of the next or previous group" : `Select the mixer ${options.bigJump} down or up`],
+		[['jump'], "Number the clients, then type a number to select one"],
+		[['volumeDown', 'volumeUp'], "Adjust volume"],
+		[['volumeDownLarge', 'volumeUpLarge'], "Adjust volume in large increments"],
+		[['volumeDownFine', 'volumeUpFine'], `Adjust volume in fine increments of ${options.fineStep}%`],
+		[['masterDown', 'masterUp'], "Adjust all clients' volumes together, keeping their balance"],
+		[['normalize'], "Set all clients in the group to the volume of its loudest"],
+		[targets.map((percent, index) => `snap:${index + 1}`), `Set volume to ${targets.slice(0, 3).map((percent) => `${percent}%`).join(", ")}, ..., ${targets[9]}%`],
+		[['typeVolume'], `Type a volume from 0 to ${options.maxVolume}`],
+		[['toggleMute'], "Toggle client mute"],
+		[['toggleGroupMute'], "Toggle group mute, leaving client mutes alone"],
+		[['toggleGroupMembersMute'], "Mute each client in the group, or restore their mutes"],
+		[['muteAll'], "Mute every group, or restore their mutes"],
+		[['latencyDown', 'latencyUp'], `Adjust client latency by ${LATENCY_STEP}ms`],
+		[['syncLatency'], "Set all clients in the group to its highest latency, to play in sync"],
+		[['rename'], "Rename client (empty to use host name)"],
+		[['moveToGroup'], "Move client to another group"],
+		[['deleteClient'], "Remove a disconnected client from the server"],
+		[['chooseStream'], "Choose the stream for the client's group"],
+		[['streamInfo'], "Show what's playing on the client's group"],
+		[['playPause'], "Play or pause the group's stream, if it can be controlled"],
+		[['previousTrack', 'nextTrack'], "Go to the previous or next track on the group's stream"],
+		[['serverInfo'], "Show the server's version and host"],
+		[['solo'], "Toggle solo (mute all other clients)"],
+		[['undo'], "Undo last volume or mute change"],
+		[['filter'], "Filter groups and clients by name; {bold}esc{/bold} clears"],
+		[['saveSnapshot'], "Save a snapshot of all volumes"],
+		[['restoreSnapshot'], "Restore a saved snapshot"],
+		[['refresh'], "Fetch the server's state now"],
+		[['reconnect'], "Reconnect to the server now"],
+		[['nextServer'], "Switch to the next server, if more than one was given"],
+		[['copyId'], "Copy the client's ID to the clipboard"],
+		[['toggleIp'], "Show or hide client IP addresses"],
+		[['toggleHideMuted'], "Show or hide muted clients"],
+		[['pin'], "Pin the client's group to the top of the list, or unpin it"],
+		[['link'], "Link the client's group so that volume changes apply to all its clients alike"],
+		[['relative'], "Show the client's group's volumes relative to its loudest client, or absolute"],
+		[['collapse'], "Collapse the client's group, or expand it; click a collapsed group to expand it"],
+		[['expandAll'], "Expand all groups"],
+		[['cycleSort'], `Change the sort order (${SORT_MODES.join(", ")})`],
+		[['quit'], "Quit; {bold}esc{/bold} closes this help box first"],
+	];
+}
+
+// Name a key as it's written in the help box
+function helpKeyName(key) {
+	if (key === 'escape') {
+		return 'esc';
+	}
+	if (/^f\d+$/.test(key)) {
+		return key.toUpperCase();
+	}
+	if (/^S-.$/.test(key)) {
+		return key.slice(2).toUpperCase();
+	}
+	return key
+		.replace(/^C-/, 'control-')
+		.replace(/^M-/, 'alt-')
+		.replace(/^S-/, 'shift-');
+}
+
+// Build the help box's table from the bindings, leaving out actions with no
+// keys
+function helpTable(bindings, options) {
+	const rows = [];
+	for (const [rowActions, description] of helpRows(options)) {
+		const keys = [];
+		for (const action of rowActions) {
+			keys.push(...Object.keys(bindings).filter((key) => bindings[key] === action));
+		}
+		if (keys.length) {
+			rows.push([keys.map((key) => `{bold}${blessed.escape(helpKeyName(key))}{/bold}`).join(", "), description]);
+		}
+	}
+	return rows.concat([
+		["{bold}alt-1{/bold}, ..., {bold}alt-9{/bold}, {bold}alt-0{/bold}", "Type a count to repeat the next move or adjustment, e.g. {bold}alt-5 l{/bold}"],
+		["{bold}click{/bold}", "Select mixer"],
+		["{bold}click{/bold}, {bold}drag{/bold} on bar", "Set volume"],
+		["{bold}scroll wheel{/bold} over mixer", "Adjust volume"],
+	]);
+}
+
+// Load the config file named in the options and work out the key bindings and
+// theme, exiting with an error if the config file is invalid
+function loadConfig(options) {
+	let config;
+	try {
+		config = readJsonFile(options.config, {});
+	} catch (error) {
+		console.error(`Could not read config file ${options.config}: ${error.message}`);
+		process.exit(1);
+	}
+
+	// Keys in the config file override the defaults; binding a key to null
+	// unbinds it
+	const bindings = Object.assign({}, DEFAULT_BINDINGS);
+	const knownActions = new Set(Object.values(DEFAULT_BINDINGS).map((action) => action.split(':')[0]));
+	const keys = config.keys || {};
+	for (const key of Object.keys(keys)) {
+		const action = keys[key];
+		const [name, argument] = action === null ? [] : action.split(':');
+		if (action === null) {
+			delete bindings[key];
+		} else if (name === 'snap' ? /^([1-9]|10)$/.test(argument) : knownActions.has(name) && argument == null) {
+			bindings[key] = action;
+		} else {
+			console.error(`Unknown action "${action}" bound to key "${key}" in ${options.config}`);
+			process.exit(1);
+		}
+	}
+
+	// The theme is layered: defaults, then the config file, then --theme
+	let theme;
+	try {
+		theme = Object.assign({}, DEFAULT_THEME, validateTheme(config.theme || {}), options.theme);
+	} catch (error) {
+		console.error(`${error.message} in ${options.config}`);
+		process.exit(1);
+	}
+
+	// Without colours, everything is drawn in the terminal's defaults
+	if (isMonochrome(options)) {
+		for (const key of Object.keys(theme)) {
+			theme[key] = 'default';
+		}
+	}
+
+	return {
+		config: config,
+		bindings: bindings,
+		theme: theme,
+	};
+}
+
+module.exports = {
+	DEFAULT_BINDINGS,
+	LATENCY_STEP,
+	helpTable,
+	loadConfig,
+};
@@ -0,0 +1,246 @@
+// A stand-in for snapserver's control interface, for trying snapmixer out
+// without a real server; run as `node mock-server.js [port]` and point
+// snapmixer at it with `--server localhost:port`
+
+const net = require('net');
+
+const port = parseInt(process.argv[2] || '1705', 10);
+
+function mockClient(id, name, ip, percent) {
+	return {
+		id: id,
+		connected: true,
+		config: {
+			instance: 1,
+			latency: 0,
+			name: '',
+			volume: {
+				muted: false,
+				percent: percent,
+			},
+		},
+		host: {
+			arch: 'x86_64',
+			ip: ip,
+			mac: id,
+			name: name,
+			os: 'Linux',
+		},
+	};
+}
+
+const status = {
+	server: {
+		groups: [
+			{
+				id: 'group-living-room',
+				name: 'Living room',
+				muted: false,
+				stream_id: 'default',
+				clients: [
+					mockClient('00:11:22:33:44:01', 'lounge', '192.168.1.11', 60),
+					mockClient('00:11:22:33:44:02', 'kitchen', '192.168.1.12', 35),
+				],
+			},
+			{
+				id: 'group-bedroom',
+				name: '',
+				muted: false,
+				stream_id: 'radio',
+				clients: [
+					mockClient('00:11:22:33:44:03', 'bedroom', '192.168.1.13', 20),
+				],
+			},
+		],
+		server: {
+			host: {
+				name: 'mock',
+				os: 'Linux',
+			},
+			snapserver: {
+				controlProtocolVersion: 1,
+				name: 'Snapserver',
+				protocolVersion: 1,
+				version: 'mock',
+			},
+		},
+		streams: [
+			{
+				id: 'default',
+				status: 'playing',
+				properties: {
+					canControl: true,
+					canGoNext: true,
+					canGoPrevious: true,
+					canPause: true,
+					canPlay: true,
+					metadata: {title: "Mock song", artist: ["Mock artist"]},
+					playbackStatus: 'playing',
+				},
+			},
+			{id: 'radio', status: 'idle'},
+		],
+	},
+};
+
+function findGroup(id) {
+	return status.server.groups.find((group) => group.id === id);
+}
+
+function findClient(id) {
+	for (const group of status.server.groups) {
+		const client = group.clients.find((candidate) => candidate.id === id);
+		if (client) {
+			return client;
+		}
+	}
+	return null;
+}
+
+// Carry out a request, returning its result and any notification to send
+// to the other connections
+function handle(method, params) {
+	const client = params && findClient(params.id);
+	const group = params && findGroup(params.id);
+	switch (method) {
+		case 'Server.GetStatus':
+			return {result: status};
+		case 'Client.GetStatus':
+			return client && {result: {client: client}};
+		case 'Client.SetVolume':
+			if (!client) {
+				return null;
+			}
+			Object.assign(client.config.volume, params.volume);
+			return {
+				result: {volume: client.config.volume},
+				notification: {method: 'Client.OnVolumeChanged', params: {id: client.id, volume: client.config.volume}},
+			};
+		case 'Client.SetName':
+			if (!client) {
+				return null;
+			}
+			client.config.name = params.name;
+			return {
+				result: {name: params.name},
+				notification: {method: 'Client.OnNameChanged', params: {id: client.id, name: params.name}},
+			};
+		case 'Client.SetLatency':
+			if (!client) {
+				return null;
+			}
+			client.config.latency = params.latency;
+			return {
+				result: {latency: params.latency},
+				notification: {method: 'Client.OnLatencyChanged', params: {id: client.id, latency: params.latency}},
+			};
+		case 'Group.GetStatus':
+			return group && {result: {group: group}};
+		case 'Group.SetMute':
+			if (!group) {
+				return null;
+			}
+			group.muted = params.mute;
+			return {
+				result: {mute: params.mute},
+				notification: {method: 'Group.OnMute', params: {id: group.id, mute: params.mute}},
+			};
+		case 'Group.SetStream':
+			if (!group) {
+				return null;
+			}
+			group.stream_id = params.stream_id;
+			return {
+				result: {stream_id: params.stream_id},
+				notification: {method: 'Group.OnStreamChanged', params: {id: group.id, stream_id: params.stream_id}},
+			};
+		case 'Stream.Control': {
+			const stream = status.server.streams.find((candidate) => candidate.id === params.id);
+			if (!stream || !stream.properties || !stream.properties.canControl) {
+				return null;
+			}
+			const properties = stream.properties;
+			if (params.command === 'playPause') {
+				properties.playbackStatus = properties.playbackStatus === 'playing' ? 'paused' : 'playing';
+			} else if (['play', 'pause', 'stop'].includes(params.command)) {
+				properties.playbackStatus = {play: 'playing', pause: 'paused', stop: 'stopped'}[params.command];
+			} else if (['next', 'previous'].includes(params.command)) {
+				properties.metadata = Object.assign({}, properties.metadata, {title: `Mock song (${params.command})`});
+			} else {
+				return null;
+			}
+			return {
+				result: 'ok',
+				notification: {method: 'Stream.OnProperties', params: {id: stream.id, properties: properties}},
+			};
+		}
+		case 'Server.DeleteClient':
+			if (!client) {
+				return null;
+			}
+			for (const other of status.server.groups) {
+				other.clients = other.clients.filter((candidate) => candidate !== client);
+			}
+			status.server.groups = status.server.groups.filter((other) => other.clients.length);
+			return {
+				result: {server: status.server},
+				notification: {method: 'Server.OnUpdate', params: {server: status.server}},
+			};
+		case 'Group.SetClients': {
+			if (!group) {
+				return null;
+			}
+			const moving = params.clients.map(findClient).filter(Boolean);
+			for (const other of status.server.groups) {
+				other.clients = other.clients.filter((candidate) => !params.clients.includes(candidate.id));
+			}
+			group.clients = moving;
+			status.server.groups = status.server.groups.filter((other) => other.clients.length);
+			return {
+				result: {server: status.server},
+				notification: {method: 'Server.OnUpdate', params: {server: status.server}},
+			};
+		}
+	}
+	return null;
+}
+
+const connections = new Set();
+
+const server = net.createServer((socket) => {
+	connections.add(socket);
+	socket.setEncoding('utf8');
+	let buffer = '';
+	socket.on('data', (data) => {
+		buffer += data;
+		const lines = buffer.split(/\r?\n/);
+		buffer = lines.pop();
+		for (const line of lines.filter((candidate) => candidate.trim().length)) {
+			let request;
+			try {
+				request = JSON.parse(line);
+			} catch (error) {
+				socket.write(JSON.stringify({jsonrpc: '2.0', id: null, error: {code: -32700, message: "Parse error"}}) + '\r\n');
+				continue;
+			}
+			const outcome = handle(request.method, request.params);
+			const response = outcome
+				? {jsonrpc: '2.0', id: request.id, result: outcome.result}
+				: {jsonrpc: '2.0', id: request.id, error: {code: -32602, message: "Invalid params or unknown method"}};
+			socket.write(JSON.stringify(response) + '\r\n');
+			if (outcome && outcome.notification) {
+				for (const other of connections) {
+					if (other !== socket) {
+						other.write(JSON.stringify(Object.assign({jsonrpc: '2.0'}, outcome.notification)) + '\r\n');
+					}
+				}
+			}
+		}
+	});
+	socket.on('close', () => connections.delete(socket));
+	socket.on('error', () => {});
+});
+
+server.listen(port, () => {
+	console.log(`Mock snapserver listening on port ${port}`);
+});
@@ -0,0 +1,117 @@
+const SnapcastClient = require('./snapcast-client');
+
+// A Snapcast client which never changes anything on the server; changes are
+// kept locally instead and applied to everything read from the server
+class ReadOnlySnapcastClient extends SnapcastClient {
+	constructor(options) {
+		super(options);
+		this.clientChanges = {};
+		this.groupChanges = {};
+		this.groupClients = {};
+		this.deletedClients = new Set();
+	}
+
+	async send(method, params, notification = false) {
+		switch (method) {
+			case 'Client.SetVolume': {
+				const changes = this.changesFor(this.clientChanges, params.id);
+				changes.volume = Object.assign({}, changes.volume, params.volume);
+				return this.localResponse(method, params, {volume: changes.volume});
+			}
+			case 'Client.SetName':
+				this.changesFor(this.clientChanges, params.id).name = params.name;
+				return this.localResponse(method, params, {name: params.name});
+			case 'Client.SetLatency':
+				this.changesFor(this.clientChanges, params.id).latency = params.latency;
+				return this.localResponse(method, params, {latency: params.latency});
+			case 'Group.SetMute':
+				this.changesFor(this.groupChanges, params.id).muted = params.mute;
+				return this.localResponse(method, params, {mute: params.mute});
+			case 'Group.SetStream':
+				this.changesFor(this.groupChanges, params.id).stream_id = params.stream_id;
+				return this.localResponse(method, params, {stream_id: params.stream_id});
+			case 'Group.SetClients':
+				this.groupClients[params.id] = params.clients;
+				return this.localResponse(method, params, {});
+			case 'Stream.Control':
+				// Nothing to show locally
+				return this.localResponse(method, params, 'ok');
+			case 'Server.DeleteClient':
+				this.deletedClients.add(params.id);
+				return this.localResponse(method, params, {});
+		}
+
+		const response = await super.send(method, params, notification);
+		if (response && response.result) {
+			if (response.result.server) {
+				this.applyToServer(response.result.server);
+			}
+			if (response.result.group) {
+				this.applyToGroup(response.result.group);
+			}
+			if (response.result.client) {
+				this.applyToClient(response.result.client);
+			}
+		}
+		return response;
+	}
+
+	changesFor(changes, id) {
+		return changes[id] || (changes[id] = {});
+	}
+
+	// Answer a request without sending it, as the server would have
+	localResponse(method, params, result) {
+		this.emit('sent', {method: method, params: params});
+		return {result: result};
+	}
+
+	applyToClient(client) {
+		const changes = this.clientChanges[client.id];
+		if (!changes) {
+			return;
+		}
+		if (changes.volume) {
+			client.config.volume = Object.assign({}, client.config.volume, changes.volume);
+		}
+		if (changes.name != null) {
+			client.config.name = changes.name;
+		}
+		if (changes.latency != null) {
+			client.config.latency = changes.latency;
+		}
+	}
+
+	applyToGroup(group) {
+		Object.assign(group, this.groupChanges[group.id]);
+		group.clients.forEach((client) => this.applyToClient(client));
+	}
+
+	applyToServer(server) {
+		// Move clients between groups as they've been moved locally
+		const clients = {};
+		for (const group of server.groups) {
+			for (const client of group.clients) {
+				clients[client.id] = client;
+			}
+		}
+		const moved = new Set([].concat(...Object.values(this.groupClients)));
+		for (const group of server.groups) {
+			if (this.groupClients[group.id]) {
+				group.clients = this.groupClients[group.id].map((id) => clients[id]).filter(Boolean);
+			} else {
+				group.clients = group.clients.filter((client) => !moved.has(client.id));
+			}
+		}
+
+		// Leave out deleted clients, and groups left empty
+		for (const group of server.groups) {
+			group.clients = group.clients.filter((client) => !this.deletedClients.has(client.id));
+		}
+		server.groups = server.groups.filter((group) => group.clients.length);
+
+		server.groups.forEach((group) => this.applyToGroup(group));
+	}
+}
+
+module.exports = ReadOnlySnapcastClient;
@@ -1,6 +1,13 @@
 const JsonRpcClient = require('./json-rpc-client');
 
 class SnapcastClient extends JsonRpcClient {
+	constructor(options) {
+		super(options);
+
+		// Highest volume which can be set
+		this.maxVolume = 100;
+	}
+
 	async getStatus() {
 		const response = await this.send('Server.GetStatus');
 		return response.result;
@@ -16,6 +23,14 @@ class SnapcastClient extends JsonRpcClient {
 		return response.result;
 	}
 
+	async setClientVolume(clientId, volume) {
+		const response = await this.send('Client.SetVolume', {
+			id: clientId,
+			volume: volume,
+		});
+		return response.result;
+	}
+
 	async getClientStatus(clientId) {
 		const response = await this.send('Client.GetStatus', {
 			id: clientId,
@@ -39,7 +54,7 @@ class SnapcastClient extends JsonRpcClient {
 		const response = await this.send('Client.SetVolume', {
 			id: clientId,
 			volume: {
-				percent: Math.min(100, Math.max(0, volume)),
+				percent: Math.min(this.maxVolume, Math.max(0, volume)),
 			},
 		});
 		return response.result;
@@ -49,6 +64,30 @@ class SnapcastClient extends JsonRpcClient {
 		return this.setVolume(clientId, (await this.getVolume(clientId)) + delta);
 	}
 
+	async setClientName(clientId, name) {
+		const response = await this.send('Client.SetName', {
+			id: clientId,
+			name: name,
+		});
+		return response.result;
+	}
+
+	async getLatency(clientId) {
+		return (await this.getClientStatus(clientId)).config.latency;
+	}
+
+	async setLatency(clientId, latency) {
+		const response = await this.send('Client.SetLatency', {
+			id: clientId,
+			latency: Math.max(0, latency),
+		});
+		return response.result;
+	}
+
+	async adjustLatency(clientId, delta) {
+		return this.setLatency(clientId, (await this.getLatency(clientId)) + delta);
+	}
+
 	async getGroupStatus(groupId) {
 		const response = await this.send('Group.GetStatus', {
 			id: groupId,
@@ -71,6 +110,37 @@ class SnapcastClient extends JsonRpcClient {
 	async toggleGroupMute(groupId) {
 		return this.setGroupMute(groupId, !await this.getGroupMute(groupId));
 	}
+
+	async setGroupStream(groupId, streamId) {
+		const response = await this.send('Group.SetStream', {
+			id: groupId,
+			stream_id: streamId,
+		});
+		return response.result;
+	}
+
+	async controlStream(streamId, command) {
+		const response = await this.send('Stream.Control', {
+			id: streamId,
+			command: command,
+		});
+		return response.result;
+	}
+
+	async deleteClient(clientId) {
+		const response = await this.send('Server.DeleteClient', {
+			id: clientId,
+		});
+		return response.result;
+	}
+
+	async setGroupClients(groupId, clientIds) {
+		const response = await this.send('Group.SetClients', {
+			id: groupId,
+			clients: clientIds,
+		});
+		return response.result;
+	}
 }
 
 module.exports = SnapcastClient;
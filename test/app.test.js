@@ -0,0 +1,263 @@
+const assert = require('assert');
+
+const {test} = require('./harness');
+const {FakeSnapcastClient, fakeClient} = require('./fake-snapcast-client');
+const {AppState, bigJumpIndex, nearestShownBar} = require('../app');
+
+const options = {
+	curve: 'linear',
+	maxVolume: 100,
+	sort: 'name',
+};
+
+const logger = {
+	log: () => {},
+};
+
+function fakeGroups(volumes) {
+	return [
+		{id: 'living-room', name: "Living room", muted: false, clients: [fakeClient('lounge', volumes[0]), fakeClient('kitchen', volumes[1])]},
+		{id: 'bedroom', name: "Bedroom", muted: false, clients: [fakeClient('bedroom', volumes[2])]},
+	];
+}
+
+function volumesOf(client) {
+	return [].concat(...client.groups.map((group) => group.clients.map((member) => member.config.volume.percent)));
+}
+
+test("adjustMasterVolume scales every client so the loudest moves by the steps", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	assert.strictEqual(await state.adjustMasterVolume(10), 70);
+	assert.deepStrictEqual(volumesOf(client), [70, 35, 23]);
+});
+
+test("adjustMasterVolume keeps the balance through repeated adjustments despite rounding", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	await state.adjustMasterVolume(10);
+	assert.strictEqual(await state.adjustMasterVolume(-10), 60);
+	assert.deepStrictEqual(volumesOf(client), [60, 30, 20]);
+});
+
+test("adjustMasterVolume brings back the balance when raised from silence", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	assert.strictEqual(await state.adjustMasterVolume(-60), 0);
+	assert.deepStrictEqual(volumesOf(client), [0, 0, 0]);
+	assert.strictEqual(await state.adjustMasterVolume(60), 60);
+	assert.deepStrictEqual(volumesOf(client), [60, 30, 20]);
+});
+
+test("adjustMasterVolume stops at the highest volume, and then goes nowhere", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([95, 50, 19]));
+	const state = new AppState(client, options, logger);
+	let limits = 0;
+	state.on('limit', () => limits++);
+	assert.strictEqual(await state.adjustMasterVolume(10), 100);
+	assert.deepStrictEqual(volumesOf(client), [100, 53, 20]);
+	assert.strictEqual(limits, 0);
+
+	const sent = client.messages.length;
+	assert.strictEqual(await state.adjustMasterVolume(1), 100);
+	assert.strictEqual(client.messages.length, sent);
+	assert.strictEqual(limits, 1);
+});
+
+test("adjustMasterVolume can be undone in one go", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	await state.adjustMasterVolume(10);
+	await state.undo();
+	assert.deepStrictEqual(volumesOf(client), [60, 30, 20]);
+});
+
+// Resolve once the state says a queued volume has been sent
+function volumeSent(state) {
+	return new Promise((resolve) => state.once('sent', resolve));
+}
+
+test("queueVolume sends only the last of several volumes queued together", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	const shown = [];
+	state.on('volume', (clientId, percent) => shown.push(percent));
+	const sent = volumeSent(state);
+	state.queueVolume('lounge', 61);
+	state.queueVolume('lounge', 62);
+	state.queueVolume('lounge', 63);
+
+	// Each is shown straight away, before any is sent
+	assert.deepStrictEqual(shown, [61, 62, 63]);
+	assert.strictEqual(state.displayedVolume(client.findClient('lounge')), 63);
+	assert.strictEqual(client.messages.length, 0);
+	assert.ok(state.hasPendingVolumes());
+
+	await sent;
+	assert.deepStrictEqual(client.messages, [{method: 'Client.SetVolume', params: {id: 'lounge', volume: {percent: 63}}}]);
+	assert.ok(!state.hasPendingVolumes());
+});
+
+test("queueVolume sends each client's volume separately", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	state.queueVolume('lounge', 50);
+	state.queueVolume('kitchen', 40);
+	state.queueVolume('kitchen', 45);
+	await volumeSent(state);
+	await volumeSent(state);
+	assert.deepStrictEqual(volumesOf(client), [50, 45, 20]);
+	assert.strictEqual(client.messages.length, 2);
+});
+
+// Resolve once the client has failed to make a change
+function changeFailed(client) {
+	return new Promise((resolve) => client.once('failed', resolve));
+}
+
+test("queueVolume retries a volume which failed to send", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	const failures = [];
+	state.on('sendFailed', (clientId, error) => failures.push(error));
+	client.failures = 1;
+	const failed = changeFailed(client);
+	const sent = volumeSent(state);
+	state.queueVolume('lounge', 61);
+	await failed;
+	await sent;
+	assert.deepStrictEqual(volumesOf(client), [61, 30, 20]);
+	assert.deepStrictEqual(failures, []);
+});
+
+test("queueVolume doesn't retry a failed volume once a newer one is queued", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	client.failures = 1;
+	const failed = changeFailed(client);
+	state.queueVolume('lounge', 61);
+	await failed;
+	// Wait for the newer one to be sent, and for the retry to come round
+	const sent = Promise.all([volumeSent(state), new Promise((resolve) => setTimeout(resolve, 600))]);
+	state.queueVolume('lounge', 62);
+	await sent;
+	assert.deepStrictEqual(client.messages, [{method: 'Client.SetVolume', params: {id: 'lounge', volume: {percent: 62}}}]);
+	assert.deepStrictEqual(volumesOf(client), [62, 30, 20]);
+});
+
+test("toggleClientMute retries a mute which failed to send", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	client.failures = 1;
+	await state.toggleClientMute('lounge');
+	assert.strictEqual(client.findClient('lounge').config.volume.muted, true);
+	assert.strictEqual(client.messages.length, 1);
+});
+
+test("toggleClientMute doesn't retry a failed mute once it's toggled again", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	client.failures = 1;
+	const failed = changeFailed(client);
+	const first = state.toggleClientMute('lounge');
+	await failed;
+	await state.toggleClientMute('lounge');
+	await state.toggleClientMute('lounge');
+	await first;
+	assert.strictEqual(client.findClient('lounge').config.volume.muted, false);
+	assert.deepStrictEqual(client.messages.map((message) => message.params.volume.muted), [true, false]);
+});
+
+test("toggleGroupMute doesn't retry a failed mute once it's toggled again", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	client.failures = 1;
+	const failed = changeFailed(client);
+	const first = state.toggleGroupMute('bedroom');
+	await failed;
+	await state.toggleGroupMute('bedroom');
+	await state.toggleGroupMute('bedroom');
+	await first;
+	assert.strictEqual(client.findGroup('bedroom').muted, false);
+	assert.deepStrictEqual(client.messages.map((message) => message.params.mute), [true, false]);
+});
+
+test("adjustVolume keeps a muted client muted, at the new volume once unmuted", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	await state.toggleClientMute('lounge');
+	const sent = volumeSent(state);
+	await state.adjustVolume('lounge', 5);
+	await sent;
+	assert.deepStrictEqual(client.findClient('lounge').config.volume, {muted: true, percent: 65});
+
+	await state.toggleClientMute('lounge');
+	assert.deepStrictEqual(client.findClient('lounge').config.volume, {muted: false, percent: 65});
+});
+
+test("undo drops volumes still waiting to be sent", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	await state.withUndo(['lounge'], [], () => state.queueVolume('lounge', 70));
+	await state.undo();
+	await new Promise((resolve) => setTimeout(resolve, 100));
+	assert.deepStrictEqual(volumesOf(client), [60, 30, 20]);
+	assert.ok(!state.hasPendingVolumes());
+});
+
+test("flushVolumes sends queued volumes straight away", async () => {
+	const client = new FakeSnapcastClient(fakeGroups([60, 30, 20]));
+	const state = new AppState(client, options, logger);
+	state.queueVolume('lounge', 61);
+	state.queueVolume('lounge', 65);
+	await state.flushVolumes();
+	assert.deepStrictEqual(volumesOf(client), [65, 30, 20]);
+	assert.strictEqual(client.messages.length, 1);
+	assert.ok(!state.hasPendingVolumes());
+
+	// Nothing more is sent once the queue's own send comes round
+	await new Promise((resolve) => setTimeout(resolve, 100));
+	assert.strictEqual(client.messages.length, 1);
+});
+
+// Bars shown in three groups, as the mixer lists them
+const bars = ['a', 'a', 'b', 'b', 'b', 'c'].map((groupId) => ({groupId: groupId}));
+
+test("bigJumpIndex moves to the start of the next or previous group", () => {
+	assert.strictEqual(bigJumpIndex(bars, 1, 1, 'group'), 2);
+	assert.strictEqual(bigJumpIndex(bars, 2, 1, 'group'), 5);
+	assert.strictEqual(bigJumpIndex(bars, 2, -1, 'group'), 0);
+	assert.strictEqual(bigJumpIndex(bars, 0, -1, 'group'), 0);
+});
+
+test("bigJumpIndex stays put in the last group", () => {
+	assert.strictEqual(bigJumpIndex(bars, 5, 1, 'group'), 5);
+});
+
+test("bigJumpIndex moves by a number of bars, stopping at the ends", () => {
+	assert.strictEqual(bigJumpIndex(bars, 1, 1, 2), 3);
+	assert.strictEqual(bigJumpIndex(bars, 4, 1, 2), 5);
+	assert.strictEqual(bigJumpIndex(bars, 1, -1, 2), 0);
+});
+
+test("bigJumpIndex starts from the first bar when none is selected", () => {
+	assert.strictEqual(bigJumpIndex(bars, -1, 1, 'group'), 0);
+	assert.strictEqual(bigJumpIndex(bars, -1, -1, 3), 0);
+});
+
+test("bigJumpIndex selects nothing when no bars are shown", () => {
+	assert.strictEqual(bigJumpIndex([], -1, 1, 'group'), -1);
+	assert.strictEqual(bigJumpIndex([], 2, -1, 'group'), -1);
+	assert.strictEqual(bigJumpIndex([], 2, 1, 3), -1);
+});
+
+test("nearestShownBar looks after a hidden bar first, then before it", () => {
+	const [x, y, z] = bars;
+	assert.strictEqual(nearestShownBar([x, y, z], [x, z], y), z);
+	assert.strictEqual(nearestShownBar([x, y, z], [x], y), x);
+	assert.strictEqual(nearestShownBar([x, y, z], [], y), null);
+});
+
+test("nearestShownBar finds nothing when there are no bars", () => {
+	assert.strictEqual(nearestShownBar([], [], bars[0]), null);
+});
@@ -0,0 +1,50 @@
+const assert = require('assert');
+
+const {test} = require('./harness');
+const {DEFAULT_PORT, parseDuration, parseListenAddress, parseServer} = require('../cli');
+
+test("parseServer takes a host with or without a port", () => {
+	assert.deepStrictEqual(parseServer('example.com'), {host: 'example.com', port: DEFAULT_PORT});
+	assert.deepStrictEqual(parseServer('example.com:1780'), {host: 'example.com', port: 1780});
+});
+
+test("parseServer takes IPv6 addresses, bracketed with a port or bare", () => {
+	assert.deepStrictEqual(parseServer('[::1]:1780'), {host: '::1', port: 1780});
+	assert.deepStrictEqual(parseServer('::1'), {host: '::1', port: DEFAULT_PORT});
+});
+
+test("parseServer takes a Unix socket which exists", () => {
+	assert.deepStrictEqual(parseServer(`unix:${__filename}`), {path: __filename});
+	assert.throws(() => parseServer('unix:/nonexistent/snapserver.sock'), /does not exist/);
+});
+
+test("parseServer rejects a missing host or a bad port", () => {
+	assert.throws(() => parseServer(':1705'), /Missing host/);
+	assert.throws(() => parseServer('example.com:99999'), /Invalid port/);
+	assert.throws(() => parseServer('example.com:http'), /Invalid port/);
+});
+
+test("parseDuration reads milliseconds, seconds and minutes, defaulting to seconds", () => {
+	assert.strictEqual(parseDuration('500ms'), 500);
+	assert.strictEqual(parseDuration('5s'), 5000);
+	assert.strictEqual(parseDuration('2m'), 120000);
+	assert.strictEqual(parseDuration('30'), 30000);
+	assert.strictEqual(parseDuration(1.5), 1500);
+});
+
+test("parseDuration rejects durations which aren't positive numbers", () => {
+	assert.throws(() => parseDuration('0s'), /must be positive/);
+	assert.throws(() => parseDuration('soon'), /Invalid duration/);
+	assert.throws(() => parseDuration('-5s'), /Invalid duration/);
+});
+
+test("parseListenAddress listens only on this machine given just a port", () => {
+	assert.deepStrictEqual(parseListenAddress('8080'), {host: '127.0.0.1', port: 8080});
+	assert.deepStrictEqual(parseListenAddress('0.0.0.0:8080'), {host: '0.0.0.0', port: 8080});
+	assert.deepStrictEqual(parseListenAddress('[::]:8080'), {host: '::', port: 8080});
+});
+
+test("parseListenAddress rejects addresses without a valid port", () => {
+	assert.throws(() => parseListenAddress('localhost'), /Invalid address/);
+	assert.throws(() => parseListenAddress('70000'), /Invalid address/);
+});
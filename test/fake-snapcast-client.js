@@ -0,0 +1,91 @@
+const SnapcastClient = require('../snapcast-client');
+
+// A client with this ID, name and volume, as the server describes them
+function fakeClient(id, percent, muted = false) {
+	return {
+		id: id,
+		connected: true,
+		config: {
+			latency: 0,
+			name: '',
+			volume: {
+				muted: muted,
+				percent: percent,
+			},
+		},
+		host: {
+			ip: '127.0.0.1',
+			name: id,
+		},
+	};
+}
+
+// A Snapcast client which answers from groups kept in memory rather than
+// from a server, applying the changes it's sent and recording them in order;
+// emits 'failed' for each change it has been told to fail instead
+class FakeSnapcastClient extends SnapcastClient {
+	constructor(groups) {
+		super({});
+		this.connected = true;
+		this.groups = groups;
+		this.messages = [];
+
+		// Number of changes still to fail, rather than apply
+		this.failures = 0;
+	}
+
+	findClient(clientId) {
+		for (const group of this.groups) {
+			const found = group.clients.find((client) => client.id === clientId);
+			if (found) {
+				return found;
+			}
+		}
+		throw new Error(`No such client ${clientId}`);
+	}
+
+	findGroup(groupId) {
+		const found = this.groups.find((group) => group.id === groupId);
+		if (!found) {
+			throw new Error(`No such group ${groupId}`);
+		}
+		return found;
+	}
+
+	async send(method, params = {}) {
+		if (!/\.Get/.test(method) && this.failures > 0) {
+			this.failures--;
+			this.emit('failed', {method: method, params: params});
+			throw new Error(`${method} failed`);
+		}
+		switch (method) {
+			case 'Server.GetStatus':
+				return {result: {server: {groups: this.groups, streams: []}}};
+			case 'Client.GetStatus':
+				return {result: {client: this.findClient(params.id)}};
+			case 'Group.GetStatus':
+				return {result: {group: this.findGroup(params.id)}};
+			case 'Client.SetVolume': {
+				const client = this.findClient(params.id);
+				client.config.volume = Object.assign({}, client.config.volume, params.volume);
+				return this.record(method, params, {volume: client.config.volume});
+			}
+			case 'Group.SetMute':
+				this.findGroup(params.id).muted = params.mute;
+				return this.record(method, params, {mute: params.mute});
+			default:
+				throw new Error(`${method} isn't faked`);
+		}
+	}
+
+	record(method, params, result) {
+		this.messages.push({method: method, params: params});
+		this.emit('sent', {method: method, params: params});
+		return {result: result};
+	}
+}
+
+module.exports = {
+	FakeSnapcastClient,
+	fakeClient,
+};
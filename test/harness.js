@@ -0,0 +1,32 @@
+// Tests registered by the test files, as name and function pairs
+const tests = [];
+
+// Register a test, which may be async and fails by throwing
+function test(name, fn) {
+	tests.push([name, fn]);
+}
+
+// Run the registered tests in order, reporting each, and fail the process if
+// any of them failed
+async function run() {
+	let failed = 0;
+	for (const [name, fn] of tests) {
+		try {
+			await fn();
+			console.log(`ok - ${name}`);
+		} catch (error) {
+			failed++;
+			console.log(`not ok - ${name}`);
+			console.log(error.stack.replace(/^/gm, '    '));
+		}
+	}
+	console.log(`${tests.length - failed} passed, ${failed} failed`);
+	if (failed) {
+		process.exitCode = 1;
+	}
+}
+
+module.exports = {
+	run,
+	test,
+};
@@ -0,0 +1,12 @@
+// Run every test file in this directory: `npm test`
+const fs = require('fs');
+const path = require('path');
+
+const {run} = require('./harness');
+
+for (const file of fs.readdirSync(__dirname).sort()) {
+	if (file.endsWith('.test.js')) {
+		require(path.join(__dirname, file));
+	}
+}
+run();
@@ -0,0 +1,36 @@
+const assert = require('assert');
+const net = require('net');
+
+const {test} = require('./harness');
+const JsonRpcClient = require('../json-rpc-client');
+
+// A server which accepts connections but never responds
+async function silentServer() {
+	const sockets = [];
+	const server = net.createServer((socket) => sockets.push(socket));
+	await new Promise((resolve) => server.listen(0, '127.0.0.1', resolve));
+	server.stop = () => {
+		for (const socket of sockets) socket.destroy();
+		server.close();
+	};
+	return server;
+}
+
+test("destroying then reconnecting rejects requests sent on the old connection", async () => {
+	const server = await silentServer();
+	const client = new JsonRpcClient({host: '127.0.0.1', port: server.address().port});
+	try {
+		await client.connect();
+		const rejected = assert.rejects(client.send('Server.GetStatus'), /Connection closed/);
+		assert.strictEqual(client.hasPendingRequests(), true);
+
+		client.destroy();
+		await client.connect();
+
+		assert.strictEqual(client.hasPendingRequests(), false);
+		await rejected;
+	} finally {
+		client.destroy();
+		server.stop();
+	}
+});
@@ -0,0 +1,20 @@
+const assert = require('assert');
+
+const {test} = require('./harness');
+const {mutedBarColor} = require('../ui');
+
+const theme = {
+	groupMuted: 'group-muted',
+	mutedText: 'muted',
+	offline: 'offline',
+};
+
+test("mutedBarColor dims offline clients first, then muted ones, then those in muted groups", () => {
+	assert.strictEqual(mutedBarColor(theme, true, true, true), 'offline');
+	assert.strictEqual(mutedBarColor(theme, false, true, true), 'muted');
+	assert.strictEqual(mutedBarColor(theme, false, false, true), 'group-muted');
+});
+
+test("mutedBarColor leaves audible clients to the gauge or focus colour", () => {
+	assert.strictEqual(mutedBarColor(theme, false, false, false), null);
+});
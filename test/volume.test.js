@@ -0,0 +1,97 @@
+const assert = require('assert');
+
+const {test} = require('./harness');
+const {adjustedVolume, formatVolume, gaugeLevel, scaledVolumes, snapTargets, withRetry} = require('../volume');
+
+test("adjustedVolume steps linearly by one percent, within 0 and the highest volume", () => {
+	assert.strictEqual(adjustedVolume(50, 1), 51);
+	assert.strictEqual(adjustedVolume(50, -3), 47);
+	assert.strictEqual(adjustedVolume(99, 5), 100);
+	assert.strictEqual(adjustedVolume(2, -5), 0);
+	assert.strictEqual(adjustedVolume(148, 5, 'linear', 150), 150);
+});
+
+test("adjustedVolume steps by one decibel on the log curve, never losing a step to rounding", () => {
+	assert.strictEqual(adjustedVolume(50, 1, 'log'), 56);
+	assert.strictEqual(adjustedVolume(50, -1, 'log'), 45);
+	assert.strictEqual(adjustedVolume(1, 1, 'log'), 2);
+	assert.strictEqual(adjustedVolume(1, -1, 'log'), 0);
+	assert.strictEqual(adjustedVolume(100, 1, 'log'), 100);
+});
+
+test("gaugeLevel fills the gauge at the highest volume and no further", () => {
+	assert.strictEqual(gaugeLevel(50), 50);
+	assert.strictEqual(gaugeLevel(75, 150), 50);
+	assert.strictEqual(gaugeLevel(150, 150), 100);
+	assert.strictEqual(gaugeLevel(120), 100);
+	assert.strictEqual(gaugeLevel(-5), 0);
+});
+
+test("formatVolume shows rounded percentages or decibels", () => {
+	assert.strictEqual(formatVolume(42), "42%");
+	assert.strictEqual(formatVolume(42.6), "43%");
+	assert.strictEqual(formatVolume(100, 'db'), "0.0dB");
+	assert.strictEqual(formatVolume(50, 'db'), "-6.0dB");
+	assert.strictEqual(formatVolume(0, 'db'), "-inf");
+});
+
+test("scaledVolumes moves the loudest by the steps and keeps the others in proportion", () => {
+	assert.deepStrictEqual(scaledVolumes({a: 60, b: 30}, 10), {
+		loudest: 60,
+		target: 70,
+		volumes: {a: 70, b: 35},
+	});
+});
+
+test("scaledVolumes stops the loudest at the limits", () => {
+	const up = scaledVolumes({a: 95, b: 50}, 10);
+	assert.strictEqual(up.target, 100);
+	assert.strictEqual(up.volumes.a, 100);
+	assert.strictEqual(Math.round(up.volumes.b), 53);
+
+	assert.deepStrictEqual(scaledVolumes({a: 3, b: 1}, -5).volumes, {a: 0, b: 0});
+	assert.strictEqual(scaledVolumes({a: 145}, 10, 'linear', 150).target, 150);
+});
+
+test("scaledVolumes brings everyone up from silence in the given balance", () => {
+	assert.deepStrictEqual(scaledVolumes({a: 0, b: 0}, 60, 'linear', 100, {a: 1, b: 0.5}).volumes, {a: 60, b: 30});
+	assert.deepStrictEqual(scaledVolumes({a: 0, b: 0}, 5).volumes, {a: 5, b: 5});
+});
+
+test("snapTargets divides the highest volume into steps", () => {
+	assert.deepStrictEqual(snapTargets(100, 10), [10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+	assert.deepStrictEqual(snapTargets(150, 20), [8, 15, 23, 30, 38, 45, 53, 60, 68, 75]);
+	assert.deepStrictEqual(snapTargets(100, 5), [20, 40, 60, 80, 100, 100, 100, 100, 100, 100]);
+});
+
+const logger = {
+	log: () => {},
+};
+
+test("withRetry runs a command again once after it fails", async () => {
+	let runs = 0;
+	const result = await withRetry(async () => {
+		if (runs++ === 0) {
+			throw new Error("Connection not OK");
+		}
+		return 'done';
+	}, () => false, logger);
+	assert.strictEqual(result, 'done');
+	assert.strictEqual(runs, 2);
+});
+
+test("withRetry gives up on a failed command which has been superseded", async () => {
+	let runs = 0;
+	const result = await withRetry(async () => {
+		runs++;
+		throw new Error("Connection not OK");
+	}, () => true, logger);
+	assert.strictEqual(result, undefined);
+	assert.strictEqual(runs, 1);
+});
+
+test("withRetry fails if the command fails again", async () => {
+	await assert.rejects(withRetry(async () => {
+		throw new Error("Connection not OK");
+	}, () => false, logger), /Connection not OK/);
+});
@@ -0,0 +1,59 @@
+// Default colours, which can be changed in the config file or with --theme
+const DEFAULT_THEME = {
+	border: '#333',
+	label: '#666',
+	text: 'white',
+	dimText: '#999',
+	gauge: '#666',
+	gaugeText: '#ccc',
+	gaugeLow: 'green',
+	gaugeMid: 'yellow',
+	gaugeHigh: 'red',
+	track: '#333',
+	focus: 'lightblue',
+	focusText: 'white',
+	focusTrack: 'blue',
+	muted: 'red',
+	mutedText: '#666',
+	groupMuted: '#555',
+	solo: 'yellow',
+	stream: '#555',
+	offline: '#444',
+	changed: 'yellow',
+	scrollbar: '#999',
+};
+
+// Check a theme's keys and colours, which may be names or hex, throwing an
+// error if any are invalid
+function validateTheme(theme) {
+	for (const key of Object.keys(theme)) {
+		if (!DEFAULT_THEME.hasOwnProperty(key)) {
+			throw new Error(`Unknown theme colour "${key}"`);
+		}
+		const color = theme[key];
+		if (!/^((light|bright)?(black|red|green|yellow|blue|magenta|cyan|white|grey|gray)|default)$/.test(color)
+			&& !/^#([0-9a-f]{3}){1,2}$/i.test(color)) {
+			throw new Error(`Invalid colour "${color}" for "${key}"`);
+		}
+	}
+	return theme;
+}
+
+// Parse a theme given on the command line as key=colour,key=colour
+function parseTheme(string) {
+	const theme = {};
+	for (const pair of string.split(',')) {
+		const [key, color] = pair.split('=').map((part) => part.trim());
+		if (!color) {
+			throw new Error(`Expected key=colour in theme, not "${pair}"`);
+		}
+		theme[key] = color;
+	}
+	return validateTheme(theme);
+}
+
+module.exports = {
+	DEFAULT_THEME,
+	parseTheme,
+	validateTheme,
+};
@@ -0,0 +1,955 @@
+const blessed = require('neo-blessed');
+const events = require('events');
+
+const {nearestShownBar} = require('./app');
+const {clientName, duplicateNames, findClient, groupSilent, groupVolume, loudestClient, serverVersion, shortId, sortClients, sortGroups} = require('./groups');
+const {formatVolume, gaugeLevel} = require('./volume');
+
+// Whether to draw without colours, using only attributes such as inverse
+function isMonochrome(options) {
+	return !options.color || !!process.env.NO_COLOR;
+}
+
+// Whether to use Unicode symbols, from the flags or else the locale
+function useUnicode(options) {
+	return options.ascii ? false
+		: options.unicode ? true
+		: /utf-?8/i.test(process.env.LC_ALL || process.env.LC_CTYPE || process.env.LANG || '');
+}
+
+// Width of the volume column for the given units
+function volumeTextWidth(units) {
+	return units === 'db' ? 7 : 4;
+}
+
+// Width of each column of groups when the number of columns is automatic
+const AUTO_COLUMN_WIDTH = 80;
+
+// Frames of the activity spinner, with or without Unicode symbols, and
+// milliseconds between them
+function spinnerFrames(unicode) {
+	return unicode
+		? ['\u280b', '\u2819', '\u2839', '\u2838', '\u283c', '\u2834', '\u2826', '\u2827', '\u2807', '\u280f']
+		: ['|', '/', '-', '\\'];
+}
+const SPINNER_INTERVAL = 100;
+
+// Width of the client name column
+const CLIENT_LABEL_WIDTH = 16;
+
+// Width of the IP address column, enough for IPv4 addresses
+const IP_TEXT_WIDTH = 15;
+
+// Width of the latency column
+const LATENCY_TEXT_WIDTH = 6;
+
+// Volumes at which gauges change from the low colour to the middle one, and
+// from the middle one to the high one, with --gradient
+const GRADIENT_LEVELS = [60, 85];
+
+// Smallest terminal in which the mixer is drawn
+const MIN_WIDTH = 20;
+const MIN_HEIGHT = 5;
+
+// Width of the gauge in each group's title row, not counting the volume
+const GROUP_GAUGE_WIDTH = 10;
+
+// Average response times in milliseconds up to which the connection is
+// shown as good, and then as ok; beyond those it's poor
+const RESPONSE_TIME_LEVELS = [100, 500];
+
+// Seconds for which a client changed by someone else is highlighted, and
+// after a change we sent within which a client's change is taken as ours
+const CHANGE_HIGHLIGHT_TIME = 2;
+
+// Hints for the most common actions, as lists of actions sharing a hint
+const FOOTER_HINTS = [
+	[['previous', 'next'], "nav"],
+	[['volumeDown', 'volumeUp'], "vol"],
+	[['toggleMute'], "mute"],
+	[['help'], "help"],
+	[['quit'], "quit"],
+];
+
+// An icon for whether a stream is playing, paused or stopped, from the
+// player's status where newer servers report it, or else the stream's
+function streamIcon(unicode, stream) {
+	const playback = stream.properties && stream.properties.playbackStatus;
+	const status = playback || stream.status;
+	if (status === 'playing') {
+		return unicode ? '\u25b6' : '>';
+	}
+	if (status === 'paused') {
+		return unicode ? '\u23f8' : '=';
+	}
+	if (status === 'stopped' || status === 'idle') {
+		return unicode ? '\u23f9' : '#';
+	}
+	return null;
+}
+
+function streamDescription(unicode, stream) {
+	if (!stream) {
+		return "(idle)";
+	}
+	const description = stream.status === 'playing' ? stream.id : `${stream.id} (idle)`;
+	const icon = streamIcon(unicode, stream);
+	return icon ? `${icon} ${description}` : description;
+}
+
+function streamMetadataLines(stream) {
+	// Newer servers put metadata in the stream's properties
+	const metadata = (stream.properties && stream.properties.metadata) || stream.meta || {};
+	return Object.keys(metadata).map((key) => {
+		const value = Array.isArray(metadata[key]) ? metadata[key].join(", ") : metadata[key];
+		return `{bold}${blessed.escape(key)}{/bold}: ${blessed.escape(String(value))}`;
+	});
+}
+
+// Colour of a client's bar whatever its volume, from the theme: for being
+// offline, or else muted itself, or else muted with its group; null if it's
+// none of these and takes the gauge or focus colour
+function mutedBarColor(theme, offline, clientMuted, groupMuted) {
+	return offline ? theme.offline
+		: clientMuted ? theme.mutedText
+		: groupMuted ? theme.groupMuted
+		: null;
+}
+
+// The mixer as drawn on the screen: a box for each group with a row for
+// each of its clients, the filter bar and the footer. Draws the server's
+// state as the app state says to show it; mouse actions are emitted for
+// the caller to carry out: 'select' with a bar clicked, 'scroll' with a bar
+// and the steps scrolled, 'drag' with a bar and the mouse event where a
+// drag along it begins, and 'expand' with a collapsed group's ID when it's
+// clicked. Emits 'redraw' when it needs to be drawn again
+class MixerView extends events.EventEmitter {
+	constructor(screen, client, state, options, theme, bindings, logger) {
+		super();
+		this.screen = screen;
+		this.client = client;
+		this.state = state;
+		this.options = options;
+		this.theme = theme;
+		this.bindings = bindings;
+		this.logger = logger;
+
+		this.monochrome = isMonochrome(options);
+		this.unicode = useUnicode(options);
+		this.volumeTextWidth = volumeTextWidth(options.units);
+		this.spinnerFrames = spinnerFrames(this.unicode);
+
+		this.form = blessed.form({
+			position: {
+				left: 0,
+				top: 0,
+				width: '100%',
+				height: '100%',
+			},
+			scrollable: true,
+			scrollbar: {
+				style: {
+					bg: theme.scrollbar,
+				},
+				track: {
+					bg: theme.track,
+				},
+			},
+			// Keyboard navigation is done by the next and previous actions
+			mouse: true,
+		});
+		screen.append(this.form);
+
+		this.filterBar = blessed.text({
+			hidden: true,
+			position: {
+				left: 0,
+				bottom: 0,
+				width: '100%',
+				height: 1,
+			},
+			style: {
+				fg: theme.dimText,
+			},
+		});
+		screen.append(this.filterBar);
+
+		this.footer = blessed.text({
+			hidden: !options.footer,
+			position: {
+				left: 0,
+				bottom: 0,
+				width: '100%',
+				height: 1,
+			},
+			style: {
+				fg: theme.dimText,
+			},
+			tags: true,
+		});
+		screen.append(this.footer);
+
+		// Shown when the server has no groups or clients
+		this.emptyMessage = blessed.text({
+			hidden: true,
+			position: {
+				left: 'center',
+				top: 'center',
+				width: 'shrink',
+				height: 1,
+			},
+			content: "No groups or clients found",
+			style: {
+				fg: theme.dimText,
+			},
+		});
+		screen.append(this.emptyMessage);
+
+		// Covers everything when the terminal is too small to draw the mixer
+		this.tooSmallMessage = blessed.box({
+			hidden: true,
+			position: {
+				left: 0,
+				top: 0,
+				width: '100%',
+				height: '100%',
+			},
+			align: 'center',
+			valign: 'middle',
+			content: "Terminal too small",
+			style: {
+				fg: theme.dimText,
+			},
+		});
+		screen.append(this.tooSmallMessage);
+
+		this.groupBoxes = {};
+		this.clientSpecs = {};
+
+		// Progress bars of the clients and collapsed groups shown, in
+		// display order
+		this.shownBars = [];
+
+		// Whether the activity spinner is showing, and its current frame
+		this.spinning = false;
+		this.spinnerFrame = 0;
+
+		// Version reported by the server, if any
+		this.reportedVersion = null;
+
+		// Label of the server in use, shown in the footer, or null
+		this.serverName = null;
+
+		// ID or name of the client to focus once there are clients, or null
+		// for the first, and whether that has been done since starting
+		this.initialFocus = null;
+		this.initiallyFocused = false;
+	}
+
+	// Name a key briefly, with arrows for the arrow keys
+	keyLabel(key) {
+		const arrows = this.unicode
+			? {up: '\u2191', down: '\u2193', left: '\u2190', right: '\u2192'}
+			: {};
+		if (arrows[key]) {
+			return arrows[key];
+		}
+		if (key === 'escape') {
+			return 'esc';
+		}
+		if (/^S-.$/.test(key)) {
+			return key.slice(2).toUpperCase();
+		}
+		return key.replace(/^C-/, '^');
+	}
+
+	// Fill the footer with as many hints as fit, using the first key bound
+	// to each action and leaving out hints for actions with no keys
+	updateFooter() {
+		const client = this.client;
+		const theme = this.theme;
+		const screen = this.screen;
+
+		// The time since the server was last heard from, which turns to the
+		// mute colour once the server has been quiet for too long
+		const quietTime = client.connected ? Date.now() - client.lastReceived : null;
+		const age = quietTime != null && `last update: ${Math.floor(quietTime / 1000)}s ago`;
+
+		// How quickly the server has been answering
+		const responseTime = client.connected ? client.responseTime : null;
+		const quality = responseTime != null && `link: ${responseTime <= RESPONSE_TIME_LEVELS[0] ? "good"
+			: responseTime <= RESPONSE_TIME_LEVELS[1] ? "ok"
+			: "poor"}`;
+
+		// The spinner, last update, link quality, read-only badge, server
+		// and server version go at the right
+		const statusParts = [
+			this.spinning && this.spinnerFrames[this.spinnerFrame],
+			age,
+			quality,
+			this.options.readOnly && "READ ONLY",
+			this.serverName,
+			this.reportedVersion && `snapserver ${this.reportedVersion}`,
+		].filter(Boolean);
+		const status = statusParts.join("  ");
+		const taggedStatus = statusParts
+			.map((part) => (part === age && quietTime > this.options.quietTimeout)
+					|| (part === quality && responseTime > RESPONSE_TIME_LEVELS[1])
+				? `{${theme.muted}-fg}${blessed.escape(part)}{/}`
+				: blessed.escape(part))
+			.join("  ");
+		const width = status ? screen.width - status.length - 2 : screen.width;
+		const hints = [];
+		for (const [hintActions, description] of FOOTER_HINTS) {
+			const keys = hintActions.map((action) => Object.keys(this.bindings).find((key) => this.bindings[key] === action));
+			if (keys.some((key) => key == null)) {
+				continue;
+			}
+			hints.push(`${keys.map((key) => this.keyLabel(key)).join(this.unicode ? '' : '/')} ${description}`);
+		}
+		let content = '';
+		for (const hint of hints) {
+			const next = content.length ? `${content}  ${hint}` : hint;
+			if (next.length > width) {
+				break;
+			}
+			content = next;
+		}
+		if (status && screen.width >= status.length) {
+			content = blessed.escape(content.padEnd(screen.width - status.length)) + taggedStatus;
+		} else {
+			content = blessed.escape(content);
+		}
+		this.footer.setContent(content);
+	}
+
+	// Advance the activity spinner while busy, and take it away once not,
+	// redrawing only while it's showing
+	spin(busy) {
+		if (!busy && !this.spinning) {
+			return;
+		}
+		this.spinning = busy;
+		this.spinnerFrame = (this.spinnerFrame + 1) % this.spinnerFrames.length;
+		this.updateFooter();
+		this.screen.render();
+	}
+
+	checkSize() {
+		if (this.screen.width < MIN_WIDTH || this.screen.height < MIN_HEIGHT) {
+			this.tooSmallMessage.show();
+			this.tooSmallMessage.setFront();
+		} else {
+			this.tooSmallMessage.hide();
+		}
+	}
+
+	// Ring the bell and flash the footer when an adjustment goes nowhere
+	// because the volume is already at its limit
+	hitLimit() {
+		if (this.options.bell) {
+			this.screen.program.bell();
+		}
+		this.footer.style.inverse = true;
+		this.screen.render();
+		setTimeout(() => {
+			this.footer.style.inverse = false;
+			this.screen.render();
+		}, 150);
+	}
+
+	// Focus a progress bar, keeping the form's idea of the selected element
+	// in step so that keyboard navigation continues from it
+	focusBar(bar) {
+		this.form._selected = bar;
+		bar.focus();
+	}
+
+	// A focusable volume gauge, as drawn for each client and for each
+	// collapsed group
+	volumeBar(position) {
+		const theme = this.theme;
+		return blessed.progressbar({
+			pch: this.unicode ? '\u2591' : '-',
+			style: {
+				bg: theme.track,
+				bar: {
+					bg: theme.gauge,
+					fg: theme.gaugeText,
+					inverse: this.monochrome,
+				},
+				focus: {
+					bg: theme.focusTrack,
+					underline: this.monochrome,
+					bar: {
+						bg: theme.focus,
+						fg: theme.focusText,
+						underline: this.monochrome,
+					},
+				},
+			},
+			position: position,
+			// filled: set later
+			input: true,
+		});
+	}
+
+	styleClientPercent(clientSpec) {
+		if (clientSpec.offline) {
+			clientSpec.percent.style.fg = this.theme.offline;
+		} else if (clientSpec.muted) {
+			clientSpec.percent.style.fg = this.theme.mutedText;
+		} else if (this.screen.focused === clientSpec.bar) {
+			clientSpec.percent.style.fg = this.theme.focus;
+		} else {
+			clientSpec.percent.style.fg = this.theme.gaugeText;
+		}
+	}
+
+	// Colour of a gauge at the given volume, by level with --gradient
+	gaugeColor(percent) {
+		if (!this.options.gradient) {
+			return this.theme.gauge;
+		}
+		return percent < GRADIENT_LEVELS[0] ? this.theme.gaugeLow
+			: percent < GRADIENT_LEVELS[1] ? this.theme.gaugeMid
+			: this.theme.gaugeHigh;
+	}
+
+	// Show a client's volume straight away, before the server confirms it
+	showVolume(clientId, percent) {
+		const clientSpec = this.clientSpecs[clientId];
+		if (!clientSpec) {
+			return;
+		}
+		if (this.state.relativeGroups.has(clientSpec.bar.groupId) || this.state.collapsedGroups.has(clientSpec.bar.groupId)) {
+			// Every client's share of the loudest may have changed, or the
+			// client is shown only in its group's header
+			this.emit('redraw');
+			return;
+		}
+		clientSpec.bar.setProgress(gaugeLevel(percent, this.options.maxVolume));
+		if (!clientSpec.offline && !clientSpec.muted) {
+			clientSpec.bar.style.bar.bg = this.gaugeColor(percent);
+		}
+		clientSpec.percent.setContent(formatVolume(percent, this.options.units));
+		this.screen.render();
+	}
+
+	// Draw everything from the server's latest state
+	async draw() {
+		const response = await this.client.getStatus();
+		this.reportedVersion = serverVersion(response);
+		const groups = sortGroups(response.server.groups, this.state.sortMode, this.state.pinnedGroups);
+		this.state.trackGroups(groups);
+		const streams = {};
+		for (const stream of response.server.streams) {
+			streams[stream.id] = stream;
+		}
+		const focusedBefore = this.screen.focused;
+		const seenGroupIds = new Set();
+		const seenClientIds = new Set();
+		let formY = 0;
+		let rowHeight = 0;
+		let placedGroups = 0;
+		const columns = this.options.columns || Math.max(1, Math.floor(this.screen.width / AUTO_COLUMN_WIDTH));
+		const columnWidth = Math.floor((this.screen.width - 1) / columns);
+		let firstVisibleBar = null;
+		const barsShown = [];
+		const allBars = [];
+
+		// Clients sharing a name are told apart by part of their IDs, which
+		// don't change between updates
+		const duplicates = duplicateNames(groups);
+		for (const group of groups) {
+			seenGroupIds.add(group.id);
+			let groupY = 0;
+			let groupSpec = this.groupBoxes[group.id];
+			if (!groupSpec) {
+				groupSpec = this.groupBoxes[group.id] = {
+					box: blessed.box({
+						position: {
+							left: 0,
+							top: formY,
+							width: '100%-1',
+							// height: set later
+						},
+						border: 'line',
+						style: {
+							border: {
+								fg: this.theme.border,
+							},
+							label: {
+								fg: this.theme.label,
+								bold: true,
+								position: {
+									left: 16,
+								},
+								left: 16,
+								rleft: 16,
+							},
+						},
+						tags: true,
+						// label: set later
+					}),
+					gauge: blessed.text({
+						position: {
+							right: 1,
+							top: -1,
+							width: 1 + GROUP_GAUGE_WIDTH + 1 + this.volumeTextWidth + 1,
+							height: 1,
+						},
+						tags: true,
+						// content: set later
+					}),
+					// Stands in for a collapsed group's clients, over the
+					// gauge on its title row, so that the group can still be
+					// selected, and its volume and mute changed
+					header: this.volumeBar({
+						right: 1 + 1 + this.volumeTextWidth + 1,
+						top: -1,
+						width: GROUP_GAUGE_WIDTH,
+						height: 1,
+					}),
+				};
+				groupSpec.header.groupHeader = true;
+				groupSpec.box.append(groupSpec.gauge);
+				groupSpec.box.append(groupSpec.header);
+				this.form.append(groupSpec.box);
+
+				groupSpec.header.on('click', () => this.emit('select', groupSpec.header));
+
+				// Click a collapsed group to expand it
+				const groupId = group.id;
+				groupSpec.box.on('click', () => {
+					if (this.state.collapsedGroups.has(groupId)) {
+						this.emit('expand', groupId);
+					}
+				});
+			}
+
+			// Keep the boxes in display order so keyboard navigation matches
+			groupSpec.box.setIndex(-1);
+
+			const labelParts = [];
+			if (this.state.collapsedGroups.has(group.id)) {
+				labelParts.push(this.unicode ? '\u25b8' : '+');
+			}
+			if (this.state.pinnedGroups.has(group.id)) {
+				labelParts.push(this.unicode ? '\u2605' : '*');
+			}
+			if (group.name.length) {
+				labelParts.push(group.name);
+			}
+			if (group.muted) {
+				labelParts.push(`{${this.theme.muted}-fg}(muted){/}`);
+			}
+			labelParts.push(`{${this.theme.stream}-fg}${streamDescription(this.unicode, streams[group.stream_id])}{/}`);
+			if (this.state.soloState && group.clients.some((client) => client.id === this.state.soloState.clientId)) {
+				labelParts.push(`{${this.theme.solo}-fg}(solo){/}`);
+			}
+			if (this.state.linkedGroups.has(group.id)) {
+				labelParts.push(`{${this.theme.dimText}-fg}(linked){/}`);
+			}
+			if (this.state.relativeGroups.has(group.id)) {
+				labelParts.push(`{${this.theme.dimText}-fg}(relative){/}`);
+			}
+			if (this.state.collapsedGroups.has(group.id)) {
+				labelParts.push(`{${this.theme.dimText}-fg}(${group.clients.length} ${group.clients.length === 1 ? "client" : "clients"}){/}`);
+			}
+			if (labelParts.length) {
+				groupSpec.box.setLabel(` ${labelParts.join(' ')} `);
+			} else {
+				groupSpec.box.removeLabel();
+			}
+
+			// Show the group's level at the right of its title row, in the
+			// mute colour if nothing in it can be heard; a collapsed group's
+			// includes changes not yet confirmed, as its header is adjusted
+			const collapsed = this.state.collapsedGroups.has(group.id);
+			const volume = collapsed ? Math.max(0, ...group.clients.map((member) => this.state.displayedVolume(member))) : groupVolume(group);
+			const filled = Math.round(gaugeLevel(volume, this.options.maxVolume) / 100 * GROUP_GAUGE_WIDTH);
+			const silent = groupSilent(group);
+			const gaugeText = (this.unicode ? '\u25ae' : '#').repeat(filled);
+			const trackText = (this.unicode ? '\u25af' : '-').repeat(GROUP_GAUGE_WIDTH - filled);
+			const volumeText = formatVolume(volume, this.options.units).padStart(this.volumeTextWidth);
+			if (collapsed) {
+				// The header's bar is drawn in place of the gauge
+				groupSpec.gauge.setContent(` ${' '.repeat(GROUP_GAUGE_WIDTH)} {${silent ? this.theme.mutedText : this.theme.dimText}-fg}${volumeText}{/} `);
+			} else {
+				groupSpec.gauge.setContent(silent
+					? ` {${this.theme.mutedText}-fg}${gaugeText}${trackText} ${volumeText}{/} `
+					: ` {${this.gaugeColor(volume)}-fg}${gaugeText}{/}{${this.theme.track}-fg}${trackText}{/} {${this.theme.dimText}-fg}${volumeText}{/} `);
+			}
+
+			// A collapsed group shows only its title row, where its header
+			// can be focused in place of its clients
+			const groupMatches = this.state.filterQuery != null && this.state.groupMatchesFilter(group);
+			const matchingClients = group.clients.filter((client) => groupMatches || this.state.clientMatchesFilter(client));
+			const visibleClients = new Set(collapsed ? [] : matchingClients.filter((client) => !this.state.hideMuted || !(client.config.volume.muted || group.muted)));
+			if (!matchingClients.length) {
+				groupSpec.box.hide();
+			} else {
+				groupSpec.box.show();
+			}
+
+			// The header acts on the group's clients together, through the
+			// first of them
+			groupSpec.header.groupId = group.id;
+			groupSpec.header.clientId = group.clients.length ? group.clients[0].id : null;
+			groupSpec.header.setIndex(-1);
+			allBars.push(groupSpec.header);
+			if (collapsed && matchingClients.length) {
+				groupSpec.header.show();
+				groupSpec.header.setProgress(gaugeLevel(volume, this.options.maxVolume));
+				groupSpec.header.style.bar.bg = silent ? this.theme.mutedText : this.gaugeColor(volume);
+				groupSpec.header.style.focus.bar.bg = silent ? this.theme.mutedText : this.theme.focus;
+				if (!firstVisibleBar) {
+					firstVisibleBar = groupSpec.header;
+				}
+				barsShown.push(groupSpec.header);
+			} else {
+				groupSpec.header.hide();
+			}
+
+			// Mark the loudest client, which the others are scaled against
+			// when volumes are adjusted together, where there's more than one
+			const loudest = group.clients.length > 1 ? loudestClient(group, (member) => this.state.exactVolume(member)) : null;
+
+			// With relative volumes, the loudest client reads 100% and the
+			// others their share of it; only what's shown changes
+			const relative = this.state.relativeGroups.has(group.id);
+			const reference = relative ? loudestClient(group, (member) => this.state.exactVolume(member)) : null;
+			const referenceLevel = reference ? this.state.exactVolume(reference) : 0;
+
+			for (const client of sortClients(group.clients, this.state.sortMode)) {
+				seenClientIds.add(client.id);
+				let clientSpec = this.clientSpecs[client.id];
+				if (!clientSpec) {
+					clientSpec = this.clientSpecs[client.id] = {
+						label: blessed.text({
+							position: {
+								left: 0,
+								top: groupY,
+								width: CLIENT_LABEL_WIDTH,
+								height: 1,
+							},
+							style: {
+								// fg: set later
+							},
+							// content: set later
+						}),
+						ip: blessed.text({
+							position: {
+								left: 17,
+								top: groupY,
+								width: IP_TEXT_WIDTH,
+								height: 1,
+							},
+							style: {
+								fg: this.theme.dimText,
+							},
+							// content: set later
+						}),
+						muteStatus: blessed.text({
+							position: {
+								// left: set later
+								top: groupY,
+								width: 1,
+								height: 1,
+							},
+							style: {
+								fg: this.theme.muted,
+								bold: true,
+							},
+							// content: set later
+						}),
+						bar: this.volumeBar({
+							// width: set later
+							height: 1,
+							top: groupY,
+							// left: set later
+						}),
+						percent: blessed.text({
+							position: {
+								left: `100%-${this.volumeTextWidth + 1 + LATENCY_TEXT_WIDTH + 2 + 1}`,
+								top: groupY,
+								width: this.volumeTextWidth,
+								height: 1,
+							},
+							align: 'right',
+							style: {
+								// fg: set later
+							},
+							// content: set later
+						}),
+						latency: blessed.text({
+							position: {
+								left: `100%-${LATENCY_TEXT_WIDTH + 2 + 1}`,
+								top: groupY,
+								width: LATENCY_TEXT_WIDTH,
+								height: 1,
+							},
+							align: 'right',
+							style: {
+								fg: this.theme.dimText,
+							},
+							// content: set later
+						}),
+					};
+					clientSpec.widgets = [
+						clientSpec.label,
+						clientSpec.ip,
+						clientSpec.muteStatus,
+						clientSpec.bar,
+						clientSpec.percent,
+						clientSpec.latency,
+					];
+
+					// Keep the percentage the same colour as the bar
+					clientSpec.bar.on('focus', () => {
+						this.styleClientPercent(clientSpec);
+						this.screen.render();
+					});
+					clientSpec.bar.on('blur', () => {
+						this.styleClientPercent(clientSpec);
+						this.screen.render();
+					});
+
+					// Mouse: click a row to focus it, click or drag along the
+					// bar to set the volume, and scroll over it to adjust
+					for (const widget of clientSpec.widgets) {
+						widget.on('click', () => this.emit('select', clientSpec.bar));
+						widget.on('wheelup', () => this.emit('scroll', clientSpec.bar, 1));
+						widget.on('wheeldown', () => this.emit('scroll', clientSpec.bar, -1));
+					}
+					clientSpec.bar.on('mousedown', (data) => this.emit('drag', clientSpec.bar, data));
+
+					// Store the client ID on the progress bar for easy access
+					clientSpec.bar.clientId = client.id;
+				}
+
+				// Put the widgets in this group's box, moving them if the
+				// client has changed group, and keep them in display order
+				for (const widget of clientSpec.widgets) {
+					if (widget.parent !== groupSpec.box) {
+						groupSpec.box.append(widget);
+					} else {
+						widget.setIndex(-1);
+					}
+				}
+				clientSpec.bar.groupId = group.id;
+				clientSpec.bar.clientName = clientName(client);
+				allBars.push(clientSpec.bar);
+
+				if (!visibleClients.has(client)) {
+					clientSpec.widgets.forEach((widget) => widget.hide());
+					continue;
+				}
+				clientSpec.widgets.forEach((widget) => {
+					widget.show();
+					widget.position.top = groupY;
+				});
+				if (!firstVisibleBar) {
+					firstVisibleBar = clientSpec.bar;
+				}
+				barsShown.push(clientSpec.bar);
+
+				// Make room for the IP address column if it's shown
+				const ipWidth = this.state.showIp ? IP_TEXT_WIDTH + 1 : 0;
+				if (!this.state.showIp) {
+					clientSpec.ip.hide();
+				}
+				clientSpec.muteStatus.position.left = 17 + ipWidth;
+				clientSpec.bar.position.left = 19 + ipWidth;
+				clientSpec.bar.position.width = `100%-${19 + ipWidth + this.volumeTextWidth + 1 + LATENCY_TEXT_WIDTH + 1 + 2 + 1}`;
+
+				let label = clientName(client);
+				if (duplicates.has(label)) {
+					const suffix = `#${shortId(client)}`;
+					label = `${label.slice(0, CLIENT_LABEL_WIDTH - suffix.length - 1)} ${suffix}`;
+				}
+
+				// Disconnected clients stay in the list but are greyed out
+				clientSpec.offline = !client.connected;
+				if (clientSpec.offline) {
+					label = `${this.unicode ? '\u26a0' : '!'} ${label}`;
+					clientSpec.label.setContent(label);
+					clientSpec.label.style.fg = this.theme.offline;
+				} else {
+					clientSpec.label.setContent(label);
+					clientSpec.label.style.fg = client.config.name.length ? this.theme.text : this.theme.dimText;
+				}
+
+				// Highlight a client whose volume or mute was changed by
+				// someone else
+				const volume = client.config.volume;
+				const previous = clientSpec.volume;
+				if (previous && (previous.percent !== volume.percent || previous.muted !== volume.muted)
+						&& Date.now() - (this.state.lastSentAt[client.id] || 0) > CHANGE_HIGHLIGHT_TIME * 1000) {
+					clientSpec.changedAt = Date.now();
+					setTimeout(() => this.emit('redraw'), CHANGE_HIGHLIGHT_TIME * 1000 + 100);
+				}
+				clientSpec.volume = volume;
+				const highlighted = clientSpec.changedAt && Date.now() - clientSpec.changedAt < CHANGE_HIGHLIGHT_TIME * 1000;
+				if (highlighted) {
+					clientSpec.label.style.fg = this.theme.changed;
+				}
+				clientSpec.label.style.bold = !!highlighted;
+
+				clientSpec.ip.setContent(client.host.ip);
+				// A client muted itself is marked apart from one muted only
+				// because its group is
+				if (client.config.volume.muted) {
+					clientSpec.muteStatus.setContent("M");
+					clientSpec.muteStatus.style.fg = this.theme.muted;
+				} else if (group.muted) {
+					clientSpec.muteStatus.setContent("G");
+					clientSpec.muteStatus.style.fg = this.theme.groupMuted;
+				} else if (client === loudest) {
+					clientSpec.muteStatus.setContent(this.unicode ? '\u25b4' : '^');
+					clientSpec.muteStatus.style.fg = this.theme.dimText;
+				} else {
+					clientSpec.muteStatus.setContent("");
+				}
+				const percent = !relative ? this.state.displayedVolume(client)
+					: referenceLevel > 0 ? Math.min(100, this.state.exactVolume(client) / referenceLevel * 100)
+					: 0;
+				// Relative volumes are shares of the loudest, so fill the
+				// gauge by them directly
+				clientSpec.bar.setProgress(gaugeLevel(percent, relative ? 100 : this.options.maxVolume));
+				clientSpec.percent.setContent(formatVolume(percent, this.options.units));
+				clientSpec.latency.setContent(`${client.config.latency}ms`);
+				clientSpec.muted = client.config.volume.muted || group.muted;
+				this.styleClientPercent(clientSpec);
+
+				// A muted client's bar still shows the level it will have when
+				// unmuted, dimmed; adjusting it changes that level without
+				// unmuting
+				const barColor = mutedBarColor(this.theme, clientSpec.offline, client.config.volume.muted, group.muted);
+				clientSpec.bar.style.bar.bg = barColor || this.gaugeColor(percent);
+				clientSpec.bar.style.focus.bar.bg = barColor || this.theme.focus;
+				groupY += 2;
+			}
+
+			if (!matchingClients.length) {
+				continue;
+			}
+
+			// Lay groups out in rows of columns, in reading order, with each
+			// row as tall as its tallest group
+			const column = placedGroups % columns;
+			if (column === 0) {
+				formY += rowHeight;
+				rowHeight = 0;
+			}
+			groupSpec.box.position.top = formY;
+			groupSpec.box.position.left = column * columnWidth;
+			groupSpec.box.position.width = column === columns - 1
+				? `100%-${column * columnWidth + 1}` // Leave room for the scrollbar
+				: columnWidth;
+			groupSpec.box.position.height = !visibleClients.size
+				? 2 // Just the top and bottom borders
+				: groupY + 1; // Group's bottom border
+			rowHeight = Math.max(rowHeight, groupSpec.box.position.height);
+			placedGroups++;
+		}
+		formY += rowHeight;
+
+		// Remove clients and groups which have gone away
+		for (const clientId of Object.keys(this.clientSpecs)) {
+			if (!seenClientIds.has(clientId)) {
+				this.clientSpecs[clientId].widgets.forEach((widget) => widget.destroy());
+				delete this.clientSpecs[clientId];
+			}
+		}
+		for (const groupId of Object.keys(this.groupBoxes)) {
+			if (!seenGroupIds.has(groupId)) {
+				this.groupBoxes[groupId].box.destroy();
+				delete this.groupBoxes[groupId];
+			}
+		}
+
+		this.shownBars = barsShown;
+
+		// The form caches its list of focusable elements; clear it so that
+		// added, removed and reordered bars are picked up
+		this.form._children = undefined;
+
+		// Keep focus on a client which has been moved to another group
+		if (focusedBefore && focusedBefore.type === 'progress-bar' && !focusedBefore.groupHeader
+				&& this.clientSpecs[focusedBefore.clientId] && this.screen.focused !== focusedBefore) {
+			this.focusBar(focusedBefore);
+		}
+
+		// If the focused client has gone, perhaps because the server restarted
+		// and it came back with a new ID, look for it by name instead
+		if (focusedBefore && focusedBefore.type === 'progress-bar' && !focusedBefore.groupHeader && !this.clientSpecs[focusedBefore.clientId]) {
+			const match = Object.values(this.clientSpecs).find((spec) => spec.bar.clientName === focusedBefore.clientName);
+			if (match) {
+				this.focusBar(match.bar);
+			}
+		}
+
+		// Once there are clients, focus the one asked for, or else the first
+		if (!this.initiallyFocused && firstVisibleBar) {
+			this.initiallyFocused = true;
+			const wanted = this.initialFocus != null ? findClient(groups, this.initialFocus) : null;
+			if (this.options.focus != null && !wanted) {
+				this.logger.log("client to focus not found:", this.options.focus);
+			}
+			this.focusBar(wanted ? this.clientSpecs[wanted.id].bar : firstVisibleBar);
+		}
+
+		// Don't leave focus on something which has been filtered out or
+		// hidden; move it to the next client shown, or else the one before
+		const focused = this.screen.focused;
+		const focusedGroup = focused && this.groupBoxes[focused.groupId];
+		if (focused && focused.type === 'progress-bar' && !focused.visible && focusedGroup && focusedGroup.header.visible) {
+			// Its group has been collapsed
+			this.focusBar(focusedGroup.header);
+		} else if (focused && focused.type === 'progress-bar' && !focused.visible && firstVisibleBar) {
+			this.focusBar(nearestShownBar(allBars, barsShown, focused) || firstVisibleBar);
+		}
+
+		if (groups.some((group) => group.clients.length)) {
+			this.emptyMessage.hide();
+		} else {
+			this.emptyMessage.show();
+		}
+
+		// Leave room at the bottom for the filter bar and footer
+		let reserved = this.options.footer ? 1 : 0;
+		this.filterBar.setContent(this.state.filterQuery == null ? '' : `Filter: ${this.state.filterQuery}`);
+		this.filterBar.position.bottom = reserved;
+		if (this.state.filterQuery == null) {
+			this.filterBar.hide();
+		} else {
+			this.filterBar.show();
+			reserved++;
+		}
+		this.form.position.height = reserved ? `100%-${reserved}` : '100%';
+		this.updateFooter();
+
+		this.screen.render();
+	}
+}
+
+module.exports = {
+	AUTO_COLUMN_WIDTH,
+	CLIENT_LABEL_WIDTH,
+	GRADIENT_LEVELS,
+	GROUP_GAUGE_WIDTH,
+	IP_TEXT_WIDTH,
+	LATENCY_TEXT_WIDTH,
+	MIN_HEIGHT,
+	MIN_WIDTH,
+	MixerView,
+	SPINNER_INTERVAL,
+	isMonochrome,
+	mutedBarColor,
+	spinnerFrames,
+	streamMetadataLines,
+	useUnicode,
+	volumeTextWidth,
+};
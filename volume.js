@@ -0,0 +1,90 @@
+// Volumes set by the digit keys 1 to 9 and then 0, as steps of the highest
+// volume divided by the number of steps
+function snapTargets(maxVolume, steps) {
+	return [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+		.map((step) => Math.min(maxVolume, Math.round(step * maxVolume / steps)));
+}
+
+// Milliseconds to wait before retrying a failed command
+const RETRY_DELAY = 500;
+
+// Format a volume percentage for display in the given units, percent or db
+function formatVolume(percent, units = 'percent') {
+	if (units === 'db') {
+		if (percent <= 0) {
+			return "-inf";
+		}
+		return `${(20 * Math.log10(percent / 100)).toFixed(1)}dB`;
+	}
+	return `${Math.round(percent)}%`;
+}
+
+// How full to draw a gauge for a volume, as a percentage, with the gauge
+// full at the given highest volume; servers can report volumes above it, so
+// the gauge stops at full while the volume itself is shown as reported
+function gaugeLevel(percent, maxVolume = 100) {
+	return Math.min(100, Math.max(0, percent * 100 / maxVolume));
+}
+
+// Apply a volume adjustment of the given number of steps, according to the
+// volume curve, linear or log, returning the new percentage up to the
+// highest volume
+function adjustedVolume(percent, steps, curve = 'linear', maxVolume = 100) {
+	if (curve === 'log') {
+		// Each step is one decibel
+		let adjusted = Math.round(Math.max(percent, 1) * Math.pow(10, steps / 20));
+
+		// Make sure a step is never lost to rounding at the quiet end
+		if (steps > 0 && adjusted <= percent) {
+			adjusted = percent + 1;
+		} else if (steps < 0 && adjusted >= percent) {
+			adjusted = percent - 1;
+		}
+		return Math.min(maxVolume, Math.max(0, adjusted));
+	}
+	return Math.min(maxVolume, Math.max(0, percent + steps));
+}
+
+// Scale volumes, by client ID, so that the loudest moves by the given steps
+// and the others keep their balance with it; from silence, each is brought up
+// to its fraction of the loudest in the given balance, or level with it if
+// it has none; gives the loudest's volume before and after, and everyone's
+// new volumes, unrounded
+function scaledVolumes(volumes, steps, curve = 'linear', maxVolume = 100, balance = {}) {
+	const loudest = Math.max(0, ...Object.values(volumes));
+	const target = adjustedVolume(loudest, steps, curve, maxVolume);
+	const scaled = {};
+	for (const id of Object.keys(volumes)) {
+		scaled[id] = loudest > 0 ? volumes[id] * target / loudest
+			: target * (balance[id] != null ? balance[id] : 1);
+	}
+	return {
+		loudest: loudest,
+		target: target,
+		volumes: scaled,
+	};
+}
+
+// Run a command, and if it fails, log it and retry it once after a short
+// delay unless it has been superseded by then
+async function withRetry(command, superseded, logger) {
+	try {
+		return await command();
+	} catch (error) {
+		logger.log("command failed, retrying:", error instanceof Error ? error.message : error);
+		await new Promise((resolve) => setTimeout(resolve, RETRY_DELAY));
+		if (superseded()) {
+			return;
+		}
+		return command();
+	}
+}
+
+module.exports = {
+	adjustedVolume,
+	formatVolume,
+	gaugeLevel,
+	scaledVolumes,
+	snapTargets,
+	withRetry,
+};